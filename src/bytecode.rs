@@ -1,8 +1,55 @@
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::fmt::{Formatter, Display};
+use std::mem::size_of;
+
+use crate::runtime::Opcode;
+
+use num::FromPrimitive;
+use num_derive::*;
+
 pub const HEADER_SIZE: &'static usize = &128;
 
 pub const CURRENT_CHES_VERSION: &'static (usize, usize, usize) = &(1, 0, 0);
 pub const MAGIC_NUMBER: &'static [u8; 8] = &[0x43u8, 0x48u8, 0x45u8, 0x53u8, 0x43u8, 0x43u8, 0x42u8, 0x43u8];
 
+// spec: 関数プール要素の形式
+// spec: | 開始アドレス (usize) | var_len (u16) | arg_len (u8) | 許可リスト | 変数の種別 | ret_len (u8) |
+// spec: - arg_len の最上位ビットは可変長引数、下位 7 ビットは固定引数の数
+// spec: - 許可リスト以降は DescriptorFlags で指定されたフィールドのみがこの順に並ぶ (指定のないフィールドは詰める)
+// spec: - ret_len は Ret で呼び出し元に残す値の要素数 (4 バイト単位; 0 は戻り値なし)、IRet / LRet は ret_len によらずそれぞれ 1 つの値を返す
+// spec: - 戻り値は呼び出し元のオペランドスタックにプッシュ順のまま置かれ、戻り値に含まれる配列の所有権は呼び出し元のフレームに移る
+// spec: 関数プール要素の拡張フィールドの有無 (ヘッダの DescriptorFlags; 各フィールドはこの順に arg_len の後ろに並ぶ)
+// note: 許可するコール番号の一覧 (u8 の個数 + 各 u8)
+pub const DESCRIPTOR_FLAG_CALL_ALLOW_LIST: u8 = 0x01;
+// note: 変数ごとの種別 (各 u8)
+pub const DESCRIPTOR_FLAG_SLOT_KINDS: u8 = 0x02;
+// note: 戻り値の要素数 (u8; 4 バイト単位)
+pub const DESCRIPTOR_FLAG_RET_LEN: u8 = 0x04;
+const DESCRIPTOR_FLAG_MASK: u8 = DESCRIPTOR_FLAG_CALL_ALLOW_LIST | DESCRIPTOR_FLAG_SLOT_KINDS | DESCRIPTOR_FLAG_RET_LEN;
+
+// note: 関数プール要素で宣言される変数の種別
+// spec: 種別は 0 から順に Int, Long, Float, Double, Ref
+#[derive(Clone, Copy, Debug, FromPrimitive, PartialEq)]
+pub enum SlotKind {
+    Int,
+    Long,
+    Float,
+    Double,
+    // note: 配列・オブジェクトの参照
+    Ref,
+}
+
+impl SlotKind {
+    pub fn width(&self) -> usize {
+        return match self {
+            SlotKind::Int | SlotKind::Float => size_of::<u32>(),
+            SlotKind::Long | SlotKind::Double => size_of::<u64>(),
+            SlotKind::Ref => size_of::<usize>(),
+        };
+    }
+}
+
 pub struct BytecodeRange {
     pub begin: usize,
     pub len: usize,
@@ -17,23 +64,589 @@ impl BytecodeRange {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+}
+
+impl Display for DiagnosticLevel {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let s = match self {
+            DiagnosticLevel::Error => "error",
+            DiagnosticLevel::Warning => "warning",
+        };
+
+        return write!(f, "{}", s);
+    }
+}
+
+// note: 読み込み時の検査結果 (offset はバイトコード先頭からの位置)
+#[derive(Clone, Debug)]
+pub struct LoadDiagnostic {
+    pub level: DiagnosticLevel,
+    pub offset: usize,
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Display for LoadDiagnostic {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        return write!(f, "{} at 0x{:0x} ({}): expected {}, actual {}", self.level, self.offset, self.field, self.expected, self.actual);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LoadDiagnostics {
+    pub items: Vec<LoadDiagnostic>,
+}
+
+impl LoadDiagnostics {
+    pub fn new() -> LoadDiagnostics {
+        return LoadDiagnostics {
+            items: Vec::new(),
+        };
+    }
+
+    pub fn push(&mut self, level: DiagnosticLevel, offset: usize, field: &'static str, expected: String, actual: String) {
+        self.items.push(LoadDiagnostic {
+            level: level,
+            offset: offset,
+            field: field,
+            expected: expected,
+            actual: actual,
+        });
+    }
+
+    pub fn has_errors(&self) -> bool {
+        return self.items.iter().any(|each_item| each_item.level == DiagnosticLevel::Error);
+    }
+}
+
+// note: インポート表の要素 (offset はバイトコード先頭からの要素の位置)
+#[derive(Clone, Debug)]
+pub struct Import {
+    pub offset: usize,
+    pub name: String,
+    pub arg_size: usize,
+    pub ret_size: usize,
+}
+
+// note: 関数プール要素の検査結果
+struct FunctionEntry {
+    start_addr: usize,
+    // note: DESCRIPTOR_FLAG_CALL_ALLOW_LIST 未指定時は None
+    call_allow_list: Option<Vec<u8>>,
+}
+
+// note: 関数プール要素の不整合 (位置, 項目名, 期待値, 実際の値)
+type DescriptorIssue = (usize, &'static str, String, String);
+
 pub struct Bytecode {
     bytes: Box<Vec<u8>>,
+    // note: インポート表 (ヘッダでプールインデックスが指定されていない場合は None)
+    imports: Option<Vec<Import>>,
+    // note: vtable のプールインデックスの一覧 (ヘッダでプールインデックスが指定されていない場合は None)
+    vtables: Option<HashSet<usize>>,
+    // note: FuncRef と MakeClosure で参照される関数のプールインデックス (InvokeDynamic と InvokeClosure の呼び出し先)
+    indirect_call_targets: HashSet<usize>,
 }
 
 impl Bytecode {
-    pub fn new(bytes: Vec<u8>) -> Bytecode {
-        return Bytecode {
+    // spec: ヘッダサイズ, マジックナンバー, ches バージョン, 関数プール要素のフラグ, インポート表, vtable の一覧, 関数の順に検査 (ヘッダサイズが不足していれば以降は検査しない)
+    pub fn new(bytes: Vec<u8>) -> (Bytecode, LoadDiagnostics) {
+        let mut bytecode = Bytecode {
             bytes: Box::new(bytes),
+            imports: None,
+            vtables: None,
+            indirect_call_targets: HashSet::new(),
+        };
+
+        let mut diagnostics = LoadDiagnostics::new();
+
+        if *HEADER_SIZE > bytecode.len() {
+            diagnostics.push(DiagnosticLevel::Error, 0, "header size", format!("{} bytes or more", *HEADER_SIZE), format!("{} bytes", bytecode.len()));
+            return (bytecode, diagnostics);
+        }
+
+        let magic_number_range = HeaderItem::MagicNumber.get_bytecode_range();
+        let magic_number_offset = magic_number_range.begin;
+        let magic_number = bytecode.get_bytes(HeaderItem::MagicNumber.get_bytecode_range()).unwrap();
+
+        if !bytecode.match_bytes(magic_number_range, &MAGIC_NUMBER.to_vec()) {
+            diagnostics.push(DiagnosticLevel::Error, magic_number_offset, "magic number", Bytecode::bytes_to_string(&MAGIC_NUMBER.to_vec()), Bytecode::bytes_to_string(&magic_number));
+        }
+
+        let ches_version_range = HeaderItem::ChesVersion.get_bytecode_range();
+        let ches_version_offset = ches_version_range.begin;
+        let ches_version = bytecode.get_bytes(ches_version_range).unwrap();
+        let expected_ches_version = vec![CURRENT_CHES_VERSION.0 as u8, CURRENT_CHES_VERSION.1 as u8, CURRENT_CHES_VERSION.2 as u8];
+
+        if ches_version != expected_ches_version {
+            diagnostics.push(DiagnosticLevel::Warning, ches_version_offset, "ches version", Bytecode::bytes_to_string(&expected_ches_version), Bytecode::bytes_to_string(&ches_version));
+        }
+
+        let descriptor_flags_offset = HeaderItem::DescriptorFlags.get_bytecode_range().begin;
+        let descriptor_flags = bytecode.bytes[descriptor_flags_offset];
+
+        if descriptor_flags & !DESCRIPTOR_FLAG_MASK != 0 {
+            diagnostics.push(DiagnosticLevel::Error, descriptor_flags_offset, "descriptor flags", format!("0x{:0x} or less", DESCRIPTOR_FLAG_MASK), format!("0x{:0x}", descriptor_flags));
+        }
+
+        let import_pool_i = bytecode.read_usize(HeaderItem::ImportPoolIndex.get_bytecode_range().begin).unwrap();
+
+        if import_pool_i != 0 {
+            match bytecode.read_imports(import_pool_i) {
+                Ok(v) => bytecode.imports = Some(v),
+                Err((offset, expected, actual)) => diagnostics.push(DiagnosticLevel::Error, offset, "import table", expected, actual),
+            }
+        }
+
+        let vtable_list_offset = HeaderItem::VtableListPoolIndex.get_bytecode_range().begin;
+        let vtable_list_pool_i = bytecode.read_usize(vtable_list_offset).unwrap();
+
+        if vtable_list_pool_i != 0 {
+            match bytecode.read_vtable_list(vtable_list_pool_i) {
+                Ok(v) => bytecode.vtables = Some(v),
+                Err((offset, expected, actual)) => diagnostics.push(DiagnosticLevel::Error, offset, "vtable list", expected, actual),
+            }
+        }
+
+        bytecode.indirect_call_targets = bytecode.verify_functions(descriptor_flags, &mut diagnostics);
+        return (bytecode, diagnostics);
+    }
+
+    // spec: エントリポイント, vtable の各メソッド, Invoke, TailInvoke, FuncRef, MakeClosure で参照される関数を順に検査
+    // spec: InvokeDynamic と InvokeClosure の呼び出し先は実行時に FuncRef か MakeClosure で参照される関数に限られ、InvokeVirtual の vtable はヘッダの一覧に限られるため、実行されうる関数は全て検査される
+    // spec: 関数プール要素の不整合は実行時の InvalidFunctionDescriptor と同じ項目名でエラーとして報告 (実行時は呼び出されるまで検出されない)
+    // note: FuncRef と MakeClosure で参照される関数のプールインデックスを返す
+    fn verify_functions(&self, descriptor_flags: u8, diagnostics: &mut LoadDiagnostics) -> HashSet<usize> {
+        // note: (関数プールインデックス, 参照元の命令またはプール要素の位置)
+        let mut func_queue = vec![(0usize, *HEADER_SIZE)];
+        let mut verified_funcs = HashSet::<usize>::new();
+        let mut indirect_call_targets = HashSet::<usize>::new();
+
+        for each_vtable_pool_i in self.vtables.iter().flatten() {
+            match self.read_vtable_methods(*each_vtable_pool_i) {
+                Ok((vtable_offset, method_pool_indexes)) => func_queue.extend(method_pool_indexes.into_iter().map(|each_pool_i| (each_pool_i, vtable_offset))),
+                Err((offset, expected, actual)) => diagnostics.push(DiagnosticLevel::Error, offset, "vtable", expected, actual),
+            }
+        }
+
+        while let Some((pool_i, ref_offset)) = func_queue.pop() {
+            if !verified_funcs.insert(pool_i) {
+                continue;
+            }
+
+            let item_offset = match self.pool_item_offset(pool_i) {
+                Some(v) => v,
+                None => {
+                    diagnostics.push(DiagnosticLevel::Error, ref_offset, "pool index", "pool index within the pool table".to_string(), format!("0x{:0x}", pool_i));
+                    continue;
+                },
+            };
+
+            // note: エントリポイントのプール要素は開始アドレスのみを持ち、コール番号の制限は実行時の allowed_calls に従う
+            let entry = if pool_i == 0 {
+                match self.read_usize(item_offset) {
+                    Some(v) => FunctionEntry {
+                        start_addr: v,
+                        call_allow_list: None,
+                    },
+                    None => {
+                        diagnostics.push(DiagnosticLevel::Error, item_offset, "entry point", "address within the bytecode".to_string(), format!("end of bytecode at 0x{:0x}", self.len()));
+                        continue;
+                    },
+                }
+            } else {
+                match self.read_function(item_offset, descriptor_flags) {
+                    Ok(v) => v,
+                    Err((offset, field, expected, actual)) => {
+                        diagnostics.push(DiagnosticLevel::Error, offset, field, expected, actual);
+                        continue;
+                    },
+                }
+            };
+
+            if entry.start_addr >= self.len() {
+                diagnostics.push(DiagnosticLevel::Error, item_offset, "start address", format!("less than 0x{:0x}", self.len()), format!("0x{:0x}", entry.start_addr));
+                continue;
+            }
+
+            self.verify_code(entry.start_addr, entry.call_allow_list.as_deref(), &mut func_queue, &mut indirect_call_targets, diagnostics);
+        }
+
+        return indirect_call_targets;
+    }
+
+    // spec: 関数プール要素は開始アドレス (usize), var_len (u16), arg_len (u8; 最上位ビットは可変長引数), DescriptorFlags で指定された拡張フィールドの順
+    fn read_function(&self, item_offset: usize, descriptor_flags: u8) -> Result<FunctionEntry, DescriptorIssue> {
+        let truncated = |offset: usize| (offset, "function descriptor", "bytes within the bytecode".to_string(), format!("end of bytecode at 0x{:0x}", self.len()));
+
+        let start_addr = self.read_usize(item_offset).ok_or_else(|| truncated(item_offset))?;
+        let mut offset = item_offset + size_of::<usize>();
+        let var_len = self.read_u16(offset).ok_or_else(|| truncated(offset))? as usize;
+        let raw_arg_len = self.read_u8(offset + 2).ok_or_else(|| truncated(offset + 2))? as usize;
+        offset += 3;
+
+        let call_allow_list = if descriptor_flags & DESCRIPTOR_FLAG_CALL_ALLOW_LIST != 0 {
+            let allow_len = self.read_u8(offset).ok_or_else(|| truncated(offset))? as usize;
+            let allow_list = self.bytes.get(offset + 1..offset + 1 + allow_len).ok_or_else(|| truncated(offset + 1))?.to_vec();
+            offset += 1 + allow_len;
+            Some(allow_list)
+        } else {
+            None
+        };
+
+        let slot_kinds = if descriptor_flags & DESCRIPTOR_FLAG_SLOT_KINDS != 0 {
+            let mut kinds = Vec::<SlotKind>::new();
+
+            for var_i in 0..var_len {
+                let kind_value = self.read_u8(offset).ok_or_else(|| truncated(offset))?;
+
+                match FromPrimitive::from_u8(kind_value) {
+                    Some(v) => kinds.push(v),
+                    None => return Err((offset, "slot kind", "0x0 - 0x4".to_string(), format!("0x{:0x} (variable {})", kind_value, var_i))),
+                }
+
+                offset += 1;
+            }
+
+            Some(kinds)
+        } else {
+            None
+        };
+
+        if descriptor_flags & DESCRIPTOR_FLAG_RET_LEN != 0 {
+            self.read_u8(offset).ok_or_else(|| truncated(offset))?;
+        }
+
+        let is_variadic = raw_arg_len & 0x80 != 0;
+        let variadic_arg_len = if slot_kinds.is_some() { 1 } else { size_of::<usize>() / size_of::<u32>() };
+        let arg_len = (raw_arg_len & 0x7f) + if is_variadic { variadic_arg_len } else { 0 };
+
+        if var_len < arg_len {
+            return Err((item_offset, "arg_len", format!("{} (var_len) or less", var_len), arg_len.to_string()));
+        }
+
+        if let (true, Some(kinds)) = (is_variadic, &slot_kinds) {
+            if kinds[arg_len - 1] != SlotKind::Ref {
+                return Err((item_offset, "slot kind", "Ref (variadic arguments)".to_string(), format!("{:?} (variable {})", kinds[arg_len - 1], arg_len - 1)));
+            }
+        }
+
+        return Ok(FunctionEntry {
+            start_addr: start_addr,
+            call_allow_list: call_allow_list,
+        });
+    }
+
+    // note: 開始アドレスから分岐先も含めて到達可能な命令を走査し、参照される関数を func_queue に追加
+    // spec: 関数自身の許可リストにないコール番号の Call とバイトコード外への分岐をエラーとして報告 (未知のオペコードや途中で途切れた命令以降は走査しない)
+    fn verify_code(&self, start_addr: usize, call_allow_list: Option<&[u8]>, func_queue: &mut Vec<(usize, usize)>, indirect_call_targets: &mut HashSet<usize>, diagnostics: &mut LoadDiagnostics) {
+        let mut pending_addrs = vec![start_addr];
+        let mut visited_addrs = HashSet::<usize>::new();
+
+        while let Some(addr) = pending_addrs.pop() {
+            let mut pc = addr;
+
+            while visited_addrs.insert(pc) {
+                let opcode_pc = pc;
+
+                let opcode = match self.read_u8(pc) {
+                    Some(v) => Opcode::from(v),
+                    None => break,
+                };
+
+                pc += 1;
+
+                let operand_len = match opcode {
+                    Opcode::Unknown | Opcode::Exit | Opcode::Ret | Opcode::IRet | Opcode::LRet | Opcode::Throw => break,
+                    Opcode::Call => {
+                        let code = match self.read_u8(pc) {
+                            Some(v) => v,
+                            None => break,
+                        };
+
+                        if let Some(allow_list) = call_allow_list {
+                            if !allow_list.contains(&code) {
+                                diagnostics.push(DiagnosticLevel::Error, opcode_pc, "call number", format!("one of {} (call allow list)", Bytecode::bytes_to_string(&allow_list.to_vec())), format!("0x{:0x}", code));
+                            }
+                        }
+
+                        1
+                    },
+                    Opcode::Invoke | Opcode::TailInvoke | Opcode::FuncRef | Opcode::MakeClosure => {
+                        let pool_i = match self.read_usize(pc) {
+                            Some(v) => v,
+                            None => break,
+                        };
+
+                        func_queue.push((pool_i, opcode_pc));
+
+                        if let Opcode::FuncRef | Opcode::MakeClosure = opcode {
+                            indirect_call_targets.insert(pool_i);
+                        }
+
+                        match opcode {
+                            Opcode::TailInvoke => break,
+                            Opcode::MakeClosure => size_of::<usize>() + 1,
+                            _ => size_of::<usize>(),
+                        }
+                    },
+                    // note: ハンドラのアドレスを持つプール要素を参照
+                    Opcode::TryEnter => {
+                        let handler_addr = match self.read_usize(pc).and_then(|pool_i| self.pool_item_offset(pool_i)).and_then(|item_offset| self.read_usize(item_offset)) {
+                            Some(v) => v,
+                            None => break,
+                        };
+
+                        self.add_branch_target(&mut pending_addrs, opcode_pc, handler_addr, 0, diagnostics);
+                        size_of::<usize>()
+                    },
+                    Opcode::Goto | Opcode::If | Opcode::IfNot
+                        | Opcode::IfICmpEq | Opcode::IfICmpNe | Opcode::IfICmpLt | Opcode::IfICmpGe | Opcode::IfICmpGt | Opcode::IfICmpLe
+                        | Opcode::IfLCmpEq | Opcode::IfLCmpNe | Opcode::IfLCmpLt | Opcode::IfLCmpGe | Opcode::IfLCmpGt | Opcode::IfLCmpLe => {
+                        let offset = match self.read_u16(pc) {
+                            Some(v) => v as i16 as isize,
+                            None => break,
+                        };
+
+                        self.add_branch_target(&mut pending_addrs, opcode_pc, pc + 2, offset, diagnostics);
+
+                        if let Opcode::Goto = opcode {
+                            break;
+                        }
+
+                        2
+                    },
+                    Opcode::GotoW | Opcode::IfW | Opcode::IfNotW => {
+                        let offset = match self.read_u32(pc) {
+                            Some(v) => v as i32 as isize,
+                            None => break,
+                        };
+
+                        self.add_branch_target(&mut pending_addrs, opcode_pc, pc + 4, offset, diagnostics);
+
+                        if let Opcode::GotoW = opcode {
+                            break;
+                        }
+
+                        4
+                    },
+                    // spec: 分岐先の表全体をここで検査するため、実行時は選択された分岐先のみを検査
+                    Opcode::TableSwitch => {
+                        let (default_offset, low, high) = match (self.read_u16(pc), self.read_u32(pc + 2), self.read_u32(pc + 6)) {
+                            (Some(default_offset), Some(low), Some(high)) => (default_offset as i16 as isize, low, high),
+                            _ => break,
+                        };
+
+                        if low > high {
+                            diagnostics.push(DiagnosticLevel::Error, opcode_pc, "switch range", format!("0x{:0x} (high) or less", high), format!("0x{:0x}", low));
+                            break;
+                        }
+
+                        let table_begin = pc + 10;
+                        let table_end = table_begin + (high - low) as usize * size_of::<i16>() + size_of::<i16>();
+
+                        if table_end > self.len() {
+                            break;
+                        }
+
+                        self.add_branch_target(&mut pending_addrs, opcode_pc, table_end, default_offset, diagnostics);
+
+                        for entry_offset in (table_begin..table_end).step_by(size_of::<i16>()) {
+                            self.add_branch_target(&mut pending_addrs, opcode_pc, table_end, self.read_u16(entry_offset).unwrap() as i16 as isize, diagnostics);
+                        }
+
+                        break;
+                    },
+                    Opcode::LookupSwitch => {
+                        let (default_offset, pair_len) = match (self.read_u16(pc), self.read_u32(pc + 2)) {
+                            (Some(default_offset), Some(pair_len)) => (default_offset as i16 as isize, pair_len as usize),
+                            _ => break,
+                        };
+
+                        let pair_size = size_of::<u32>() + size_of::<i16>();
+                        let pairs_begin = pc + 6;
+                        let pairs_end = pairs_begin + pair_len * pair_size;
+
+                        if pairs_end > self.len() {
+                            break;
+                        }
+
+                        self.add_branch_target(&mut pending_addrs, opcode_pc, pairs_end, default_offset, diagnostics);
+
+                        for pair_offset in (pairs_begin..pairs_end).step_by(pair_size) {
+                            self.add_branch_target(&mut pending_addrs, opcode_pc, pairs_end, self.read_u16(pair_offset + size_of::<u32>()).unwrap() as i16 as isize, diagnostics);
+                        }
+
+                        break;
+                    },
+                    // note: 呼び出し先のメソッドはヘッダの vtable の一覧から検査
+                    Opcode::InvokeVirtual => {
+                        if self.vtables.is_none() {
+                            diagnostics.push(DiagnosticLevel::Error, opcode_pc, "vtable list", "vtable list pool index in the header (InvokeVirtual)".to_string(), "0x0".to_string());
+                        }
+
+                        2
+                    },
+                    Opcode::BPush => 1,
+                    Opcode::SPush | Opcode::Load | Opcode::Load2 | Opcode::Store | Opcode::Store2
+                        | Opcode::GLoad | Opcode::GLoad2 | Opcode::GStore | Opcode::GStore2 => 2,
+                    Opcode::Inc => 3,
+                    Opcode::IPush | Opcode::FPush => 4,
+                    Opcode::LPush | Opcode::DPush => 8,
+                    Opcode::QPush => 16,
+                    Opcode::BAPush | Opcode::SAPush | Opcode::IAPush | Opcode::LAPush
+                        | Opcode::Ldc | Opcode::Ldc2 | Opcode::LdStr | Opcode::New => size_of::<usize>(),
+                    Opcode::GetField | Opcode::PutField => size_of::<usize>() + 2,
+                    _ => 0,
+                };
+
+                pc += operand_len;
+            }
+        }
+    }
+
+    // spec: インポート表のプール要素は要素数 (u8; 0x80 以下) と各要素の (名前のバイト長 u8, 名前 (UTF-8), 引数のバイトサイズ u16, 戻り値のバイトサイズ u16)
+    // spec: Call の番号 0x80 + i がインポート表の i 番目の要素を指す
+    fn read_imports(&self, pool_i: usize) -> Result<Vec<Import>, (usize, String, String)> {
+        let mut offset = match self.pool_item_offset(pool_i) {
+            Some(v) => v,
+            None => return Err((HeaderItem::ImportPoolIndex.get_bytecode_range().begin, "pool index within the pool table".to_string(), format!("0x{:0x}", pool_i))),
+        };
+
+        let import_len = self.read_u8(offset).ok_or_else(|| self.truncated_error(offset))? as usize;
+
+        if import_len > 0x80 {
+            return Err((offset, "0x80 or less imports".to_string(), format!("0x{:0x}", import_len)));
+        }
+
+        offset += 1;
+        let mut imports = Vec::<Import>::new();
+
+        for _ in 0..import_len {
+            let import_offset = offset;
+            let name_len = self.read_u8(offset).ok_or_else(|| self.truncated_error(offset))? as usize;
+            let name_bytes = self.bytes.get(offset + 1..offset + 1 + name_len).ok_or_else(|| self.truncated_error(offset + 1))?;
+            let name = String::from_utf8(name_bytes.to_vec()).map_err(|_| (offset + 1, "UTF-8 name".to_string(), Bytecode::bytes_to_string(&name_bytes.to_vec())))?;
+            offset += 1 + name_len;
+
+            let arg_size = self.read_u16(offset).ok_or_else(|| self.truncated_error(offset))? as usize;
+            let ret_size = self.read_u16(offset + 2).ok_or_else(|| self.truncated_error(offset + 2))? as usize;
+            offset += 4;
+
+            imports.push(Import {
+                offset: import_offset,
+                name: name,
+                arg_size: arg_size,
+                ret_size: ret_size,
+            });
+        }
+
+        return Ok(imports);
+    }
+
+    // spec: vtable の一覧のプール要素は要素数 (u16) と各 vtable のプールインデックス (usize)
+    fn read_vtable_list(&self, pool_i: usize) -> Result<HashSet<usize>, (usize, String, String)> {
+        let offset = match self.pool_item_offset(pool_i) {
+            Some(v) => v,
+            None => return Err((HeaderItem::VtableListPoolIndex.get_bytecode_range().begin, "pool index within the pool table".to_string(), format!("0x{:0x}", pool_i))),
+        };
+
+        let vtable_len = self.read_u16(offset).ok_or_else(|| self.truncated_error(offset))? as usize;
+        let mut vtables = HashSet::<usize>::new();
+
+        for vtable_i in 0..vtable_len {
+            let each_offset = offset + 2 + vtable_i * size_of::<usize>();
+            vtables.insert(self.read_usize(each_offset).ok_or_else(|| self.truncated_error(each_offset))?);
+        }
+
+        return Ok(vtables);
+    }
+
+    // note: vtable のプール要素の位置と各メソッドの関数プールインデックスを返す
+    // spec: vtable のプール要素はメソッド数 (u16) と各メソッドの関数プールインデックス (usize)
+    fn read_vtable_methods(&self, pool_i: usize) -> Result<(usize, Vec<usize>), (usize, String, String)> {
+        let offset = match self.pool_item_offset(pool_i) {
+            Some(v) => v,
+            None => return Err((HeaderItem::VtableListPoolIndex.get_bytecode_range().begin, "pool index within the pool table".to_string(), format!("0x{:0x}", pool_i))),
         };
+
+        let method_len = self.read_u16(offset).ok_or_else(|| self.truncated_error(offset))? as usize;
+        let mut method_pool_indexes = Vec::<usize>::new();
+
+        for method_i in 0..method_len {
+            let each_offset = offset + 2 + method_i * size_of::<usize>();
+            method_pool_indexes.push(self.read_usize(each_offset).ok_or_else(|| self.truncated_error(each_offset))?);
+        }
+
+        return Ok((offset, method_pool_indexes));
+    }
+
+    fn truncated_error(&self, offset: usize) -> (usize, String, String) {
+        return (offset, "bytes within the bytecode".to_string(), format!("end of bytecode at 0x{:0x}", self.len()));
+    }
+
+    pub fn imports(&self) -> Option<&[Import]> {
+        return self.imports.as_deref();
+    }
+
+    pub fn vtables(&self) -> Option<&HashSet<usize>> {
+        return self.vtables.as_ref();
+    }
+
+    pub fn indirect_call_targets(&self) -> &HashSet<usize> {
+        return &self.indirect_call_targets;
+    }
+
+    pub fn read_u8(&self, offset: usize) -> Option<u8> {
+        return self.bytes.get(offset).copied();
+    }
+
+    // note: 分岐先 (base からの相対位置) を検査して走査対象に追加
+    fn add_branch_target(&self, pending_addrs: &mut Vec<usize>, opcode_pc: usize, base: usize, offset: isize, diagnostics: &mut LoadDiagnostics) {
+        let target = base as isize + offset;
+
+        if 0 > target || target as usize >= self.len() {
+            diagnostics.push(DiagnosticLevel::Error, opcode_pc, "branch target", format!("0x0 - 0x{:0x}", self.len() - 1), format!("{}0x{:0x}", if 0 > target { "-" } else { "" }, target.unsigned_abs()));
+        } else {
+            pending_addrs.push(target as usize);
+        }
+    }
+
+    // spec: ヘッダの数値, プールテーブル, プール要素, 命令のオペランドは全て実行時と同じくネイティブのバイト順で読み込む
+    pub fn read_u16(&self, offset: usize) -> Option<u16> {
+        return Some(u16::from_ne_bytes(self.bytes.get(offset..offset.checked_add(2)?)?.try_into().unwrap()));
+    }
+
+    pub fn read_u32(&self, offset: usize) -> Option<u32> {
+        return Some(u32::from_ne_bytes(self.bytes.get(offset..offset.checked_add(4)?)?.try_into().unwrap()));
+    }
+
+    pub fn read_usize(&self, offset: usize) -> Option<usize> {
+        return Some(usize::from_ne_bytes(self.bytes.get(offset..offset.checked_add(size_of::<usize>())?)?.try_into().unwrap()));
+    }
+
+    // note: プールインデックスに対応するプール要素の位置
+    pub fn pool_item_offset(&self, pool_i: usize) -> Option<usize> {
+        let table_offset = pool_i.checked_mul(size_of::<usize>())?.checked_add(*HEADER_SIZE)?;
+        return self.read_usize(table_offset);
     }
 
+    #[cfg(feature = "trace")]
     pub fn print(&self) {
         println!("- Ches Bytecode -");
         println!();
         println!("MAGIC NUMBER\t{}", Bytecode::bytes_to_string(&self.get_bytes(HeaderItem::MagicNumber.get_bytecode_range()).unwrap()));
         println!("CODE NAME\t{}", Bytecode::bytes_to_string(&self.get_bytes(HeaderItem::CodeName.get_bytecode_range()).unwrap()));
         println!("CHES VERSION\t{}", Bytecode::bytes_to_string(&self.get_bytes(HeaderItem::ChesVersion.get_bytecode_range()).unwrap()));
+        println!("GLOBAL LEN\t{}", Bytecode::bytes_to_string(&self.get_bytes(HeaderItem::GlobalLen.get_bytecode_range()).unwrap()));
+        println!("DESC FLAGS\t{}", Bytecode::bytes_to_string(&self.get_bytes(HeaderItem::DescriptorFlags.get_bytecode_range()).unwrap()));
+        println!("IMPORT POOL\t{}", Bytecode::bytes_to_string(&self.get_bytes(HeaderItem::ImportPoolIndex.get_bytecode_range()).unwrap()));
+        println!("VTABLE LIST\t{}", Bytecode::bytes_to_string(&self.get_bytes(HeaderItem::VtableListPoolIndex.get_bytecode_range()).unwrap()));
         println!();
         println!("{}", Bytecode::bytes_to_string(&*self.bytes));
         println!();
@@ -70,10 +683,21 @@ impl Bytecode {
     }
 }
 
+// note: CodeName は現状 Bytecode::print でのみ使用
+// spec: 数値のフィールドはプール要素や命令のオペランドと同じくネイティブのバイト順 (バイトコードは実行するホストと同じバイト順で生成する)
+#[cfg_attr(not(feature = "trace"), allow(dead_code))]
 pub enum HeaderItem {
     MagicNumber,
     CodeName,
     ChesVersion,
+    // spec: グローバル変数の要素数 (u16, 4 バイト単位)
+    GlobalLen,
+    // spec: 関数プール要素の拡張フィールドの有無 (u8; DESCRIPTOR_FLAG_* の論理和)
+    DescriptorFlags,
+    // spec: インポート表のプールインデックス (usize; 0 はインポート表なし)
+    ImportPoolIndex,
+    // spec: vtable の一覧のプールインデックス (usize; 0 は一覧なし; InvokeVirtual を使う場合は必須)
+    VtableListPoolIndex,
 }
 
 impl HeaderItem {
@@ -82,6 +706,10 @@ impl HeaderItem {
             HeaderItem::MagicNumber => (0, 8),
             HeaderItem::CodeName => (8, 8),
             HeaderItem::ChesVersion => (16, 3),
+            HeaderItem::GlobalLen => (19, 2),
+            HeaderItem::DescriptorFlags => (21, 1),
+            HeaderItem::ImportPoolIndex => (22, 8),
+            HeaderItem::VtableListPoolIndex => (30, 8),
         };
 
         return BytecodeRange::new(begin, len);
@@ -1,25 +1,129 @@
-pub mod bytecode;
-pub mod runtime;
+mod bytecode;
+mod runtime;
 
+use crate::bytecode::Bytecode;
 use crate::runtime::*;
 
 use rustnutlib::file::*;
 
-pub struct ChesVM {}
+use std::any::Any;
+use std::fmt::{Formatter, Display};
 
-impl ChesVM {
-    pub fn new() -> ChesVM {
-        return ChesVM {};
+// note: 埋め込み側に公開する API (内部モジュールは非公開)
+pub use crate::bytecode::CURRENT_CHES_VERSION;
+pub use crate::bytecode::{DESCRIPTOR_FLAG_CALL_ALLOW_LIST, DESCRIPTOR_FLAG_SLOT_KINDS, DESCRIPTOR_FLAG_RET_LEN};
+pub use crate::bytecode::{DiagnosticLevel, Import, LoadDiagnostic, LoadDiagnostics};
+pub use crate::runtime::DescriptorError;
+pub use crate::runtime::ExitStatus;
+pub use crate::runtime::FloatToIntPolicy;
+pub use crate::runtime::{HostCall, HostContext};
+pub use crate::runtime::HOST_CALL_NUMBER_START;
+pub use crate::runtime::IoErrorPolicy;
+pub use crate::runtime::InterpreterConfig as VmConfig;
+pub use crate::runtime::RunReport;
+pub use crate::runtime::SandboxProfile;
+pub use crate::runtime::Opcode;
+
+// note: バイトコードファイルを読み込めなかった場合のエラー
+#[derive(Clone, Debug)]
+pub enum LoadError {
+    // note: 読み込めなかったファイルのパス
+    FileRead(String),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        return match self {
+            LoadError::FileRead(path) => write!(f, "failed to read bytecode file '{}'", path),
+        };
+    }
+}
+
+// note: 読み込み済みのバイトコード (読み込み時に検査され、同じモジュールを複数の Vm で実行可能)
+#[derive(Clone)]
+pub struct Module {
+    bytes: Vec<u8>,
+    // note: 読み込み時の検査結果
+    diagnostics: LoadDiagnostics,
+}
+
+impl Module {
+    // note: ヘッダと、エントリポイント・vtable の各メソッドから到達可能な関数のプール要素・コードを検査 (結果は diagnostics で参照)
+    // note: 間接呼び出し (InvokeDynamic, InvokeClosure, InvokeVirtual) の呼び出し先は実行時に検査済みの関数に限られる
+    pub fn from_bytes(bytes: Vec<u8>) -> Module {
+        let diagnostics = Bytecode::new(bytes.clone()).1;
+
+        return Module {
+            bytes: bytes,
+            diagnostics: diagnostics,
+        };
     }
 
-    pub fn run(&self, chesc_file_path: &str) -> FileResult<ExitStatus> {
-        let file_bytes = match FileMan::read_all_bytes(chesc_file_path) {
-            Ok(v) => v,
-            Err(e) => return Err(e),
+    // note: バイトコードファイルを読み込んで from_bytes と同様に検査 (検査のエラーは Err ではなく diagnostics で参照)
+    pub fn load(chesc_file_path: &str) -> Result<Module, LoadError> {
+        return match FileMan::read_all_bytes(chesc_file_path) {
+            Ok(v) => Ok(Module::from_bytes(v)),
+            Err(_) => Err(LoadError::FileRead(chesc_file_path.to_string())),
         };
+    }
+
+    // note: 読み込み時の検査結果 (実行時にも同じ検査が行われ、エラーがあれば InvalidBytecode で終了)
+    pub fn diagnostics(&self) -> LoadDiagnostics {
+        return self.diagnostics.clone();
+    }
+}
+
+// note: 設定とホストコールを保持する実行環境 (実行ごとにスタック・ヒープ・定数領域を新たに確保するため、1 つの Vm で複数のモジュールを実行可能)
+#[derive(Clone, Default)]
+pub struct Vm {
+    config: VmConfig,
+}
+
+impl Vm {
+    // note: 既定の設定 (VmConfig::new) の Vm
+    pub fn new() -> Vm {
+        return Vm::with_config(VmConfig::new());
+    }
+
+    // note: ホストコールは config に登録してから渡す
+    pub fn with_config(config: VmConfig) -> Vm {
+        return Vm {
+            config: config,
+        };
+    }
+
+    // note: バイトコードファイルを読み込んで実行 (ファイルを読み込めなかった場合のみ Err)
+    pub fn run(&self, chesc_file_path: &str) -> Result<ExitStatus, LoadError> {
+        let module = Module::load(chesc_file_path)?;
+
+        return Ok(self.run_module(&module));
+    }
+
+    // note: 検査のエラーがあれば実行せずに InvalidBytecode を返す
+    pub fn run_module(&self, module: &Module) -> ExitStatus {
+        unsafe {
+            return Interpreter::launch(module.bytes.clone(), &self.config);
+        }
+    }
+
+    // note: run_module に加え、記述子のエラーの詳細や設定に応じた計測結果を RunReport で返す
+    pub fn run_module_with_report(&self, module: &Module) -> (ExitStatus, RunReport) {
+        unsafe {
+            return Interpreter::launch_with_report(module.bytes.clone(), &self.config, None);
+        }
+    }
+
+    // note: モジュールの検査に加え、インポート表の名前をこの Vm に登録されたホストコールと照合 (実行時にも同じ照合が行われる)
+    pub fn diagnostics(&self, module: &Module) -> LoadDiagnostics {
+        let (bytecode, mut diagnostics) = Bytecode::new(module.bytes.clone());
+        self.config.resolve_imports(bytecode.imports(), &mut diagnostics);
+        return diagnostics;
+    }
 
+    // note: ホストコールのハンドラから HostContext::user_data で参照できる値を渡して実行 (リクエストごとの状態などに使用)
+    pub fn run_module_with_user_data(&self, module: &Module, user_data: &mut dyn Any) -> (ExitStatus, RunReport) {
         unsafe {
-            return Ok(Interpreter::launch(file_bytes));
+            return Interpreter::launch_with_report(module.bytes.clone(), &self.config, Some(user_data));
         }
     }
 }
@@ -1,17 +1,31 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Formatter, Display};
+#[cfg(feature = "trace")]
 use std::slice::from_raw_parts;
 use std::mem::size_of;
+use std::ptr::copy_nonoverlapping;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 use crate::bytecode::*;
 
+#[cfg(feature = "trace")]
 use colored::*;
 
-use libc::{c_void, malloc, free, read, write};
+use libc::{c_int, c_uint, c_void, malloc, free, read, write, open, close, lseek};
 
-use num::FromPrimitive;
+use num::{BigInt, FromPrimitive, ToPrimitive, Zero};
 use num_derive::*;
 
-#[derive(FromPrimitive)]
+// note: trace フィーチャ無効時はトレース出力を除去
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "trace")]
+        println!($($arg)*);
+    };
+}
+
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, PartialEq)]
 pub enum ExitStatus {
     Success,
     UnknownOpcode,
@@ -22,6 +36,21 @@ pub enum ExitStatus {
     ArrayAccessViolation,
     ArithmeticOverflow,
     DivideByZero,
+    InvalidFloatOperation,
+    InvalidFunctionDescriptor,
+    InstructionLimitExceeded,
+    UncaughtException,
+    HostCallFailed,
+    CallNotPermitted,
+    NarrowingOverflow,
+    GlobalAccessViolation,
+    InvalidLayoutDescriptor,
+    InvalidBytecode,
+    StackCorruption,
+    IoFailed,
+    FuelExhausted,
+    InvalidHostCall,
+    HeapLimitExceeded,
     Unknown,
 }
 
@@ -37,6 +66,21 @@ impl Display for ExitStatus {
             ExitStatus::ArrayAccessViolation => "ARRAY_ACCESS_VIOLATION",
             ExitStatus::ArithmeticOverflow => "ARITHMETIC_OVERFLOW",
             ExitStatus::DivideByZero => "DIVIDE_BY_ZERO",
+            ExitStatus::InvalidFloatOperation => "INVALID_FLOAT_OPERATION",
+            ExitStatus::InvalidFunctionDescriptor => "INVALID_FUNCTION_DESCRIPTOR",
+            ExitStatus::InstructionLimitExceeded => "INSTRUCTION_LIMIT_EXCEEDED",
+            ExitStatus::UncaughtException => "UNCAUGHT_EXCEPTION",
+            ExitStatus::HostCallFailed => "HOST_CALL_FAILED",
+            ExitStatus::CallNotPermitted => "CALL_NOT_PERMITTED",
+            ExitStatus::NarrowingOverflow => "NARROWING_OVERFLOW",
+            ExitStatus::GlobalAccessViolation => "GLOBAL_ACCESS_VIOLATION",
+            ExitStatus::InvalidLayoutDescriptor => "INVALID_LAYOUT_DESCRIPTOR",
+            ExitStatus::InvalidBytecode => "INVALID_BYTECODE",
+            ExitStatus::StackCorruption => "STACK_CORRUPTION",
+            ExitStatus::IoFailed => "IO_FAILED",
+            ExitStatus::FuelExhausted => "FUEL_EXHAUSTED",
+            ExitStatus::InvalidHostCall => "INVALID_HOST_CALL",
+            ExitStatus::HeapLimitExceeded => "HEAP_LIMIT_EXCEEDED",
             ExitStatus::Unknown => "UNKNOWN",
         };
 
@@ -53,6 +97,14 @@ impl From<u32> for ExitStatus {
     }
 }
 
+impl ExitStatus {
+    // spec: 例外ハンドラで捕捉できるのは演算のトラップ (オーバーフロー, 0 除算, 縮小変換, NaN) と関数ごとの命令数の上限超過のみ
+    // spec: バイトコード・スタック・配列の不整合やヒープ・命令数 (max_instructions) の上限超過などは捕捉せずに終了 (破損した状態での続行や保護の無効化を防ぐため)
+    pub fn is_catchable(&self) -> bool {
+        return matches!(self, ExitStatus::ArithmeticOverflow | ExitStatus::DivideByZero | ExitStatus::NarrowingOverflow | ExitStatus::InvalidFloatOperation | ExitStatus::InstructionLimitExceeded);
+    }
+}
+
 #[derive(FromPrimitive)]
 pub enum Opcode {
     Unknown,
@@ -105,6 +157,147 @@ pub enum Opcode {
     Goto,
     If,
     IfNot,
+    FPush,
+    DPush,
+    FAdd,
+    DAdd,
+    FSub,
+    DSub,
+    FMul,
+    DMul,
+    FDiv,
+    DDiv,
+    IAnd,
+    LAnd,
+    IOr,
+    LOr,
+    IXor,
+    LXor,
+    INot,
+    LNot,
+    IShl,
+    LShl,
+    IShr,
+    LShr,
+    IAShr,
+    LAShr,
+    IRem,
+    LRem,
+    INeg,
+    LNeg,
+    I2L,
+    L2I,
+    B2I,
+    I2S,
+    Inc,
+    TableSwitch,
+    LookupSwitch,
+    Swap,
+    Swap2,
+    DupX1,
+    DupX2,
+    FCmpT,
+    DCmpT,
+    FuncRef,
+    InvokeDynamic,
+    TailInvoke,
+    Throw,
+    TryEnter,
+    TryLeave,
+    IRet,
+    LRet,
+    GotoW,
+    IfW,
+    IfNotW,
+    IfICmpEq,
+    IfICmpNe,
+    IfICmpLt,
+    IfICmpGe,
+    IfICmpGt,
+    IfICmpLe,
+    IfLCmpEq,
+    IfLCmpNe,
+    IfLCmpLt,
+    IfLCmpGe,
+    IfLCmpGt,
+    IfLCmpLe,
+    Ldc,
+    Ldc2,
+    LdStr,
+    IMin,
+    IMax,
+    LMin,
+    LMax,
+    IAbs,
+    LAbs,
+    IRotl,
+    IRotr,
+    LRotl,
+    LRotr,
+    IPopcnt,
+    LPopcnt,
+    IClz,
+    LClz,
+    ICtz,
+    LCtz,
+    ArrayCopy,
+    BAFill,
+    SAFill,
+    IAFill,
+    LAFill,
+    I2F,
+    F2I,
+    L2D,
+    D2L,
+    FCmpL,
+    FCmpG,
+    DCmpL,
+    DCmpG,
+    IAddSat,
+    LAddSat,
+    ISubSat,
+    LSubSat,
+    IMulSat,
+    LMulSat,
+    IALoadAtomic,
+    LALoadAtomic,
+    IAStoreAtomic,
+    LAStoreAtomic,
+    IACas,
+    LACas,
+    IAFetchAdd,
+    LAFetchAdd,
+    GLoad,
+    GLoad2,
+    GStore,
+    GStore2,
+    GetField,
+    PutField,
+    New,
+    ICmp,
+    LCmp,
+    QPush,
+    QAdd,
+    QSub,
+    QMul,
+    QDiv,
+    IVAdd,
+    LVAdd,
+    FVAdd,
+    DVAdd,
+    IVMul,
+    LVMul,
+    FVMul,
+    DVMul,
+    IVEq,
+    LVEq,
+    MakeClosure,
+    InvokeClosure,
+    CanarySet,
+    CanaryCheck,
+    InvokeVirtual,
+    Intern,
+    LdInterned,
 }
 
 impl Display for Opcode {
@@ -160,6 +353,147 @@ impl Display for Opcode {
             Opcode::Goto => "goto",
             Opcode::If => "if",
             Opcode::IfNot => "ifnot",
+            Opcode::FPush => "fpush",
+            Opcode::DPush => "dpush",
+            Opcode::FAdd => "fadd",
+            Opcode::DAdd => "dadd",
+            Opcode::FSub => "fsub",
+            Opcode::DSub => "dsub",
+            Opcode::FMul => "fmul",
+            Opcode::DMul => "dmul",
+            Opcode::FDiv => "fdiv",
+            Opcode::DDiv => "ddiv",
+            Opcode::IAnd => "iand",
+            Opcode::LAnd => "land",
+            Opcode::IOr => "ior",
+            Opcode::LOr => "lor",
+            Opcode::IXor => "ixor",
+            Opcode::LXor => "lxor",
+            Opcode::INot => "inot",
+            Opcode::LNot => "lnot",
+            Opcode::IShl => "ishl",
+            Opcode::LShl => "lshl",
+            Opcode::IShr => "ishr",
+            Opcode::LShr => "lshr",
+            Opcode::IAShr => "iashr",
+            Opcode::LAShr => "lashr",
+            Opcode::IRem => "irem",
+            Opcode::LRem => "lrem",
+            Opcode::INeg => "ineg",
+            Opcode::LNeg => "lneg",
+            Opcode::I2L => "i2l",
+            Opcode::L2I => "l2i",
+            Opcode::B2I => "b2i",
+            Opcode::I2S => "i2s",
+            Opcode::Inc => "inc",
+            Opcode::TableSwitch => "tableswitch",
+            Opcode::LookupSwitch => "lookupswitch",
+            Opcode::Swap => "swap",
+            Opcode::Swap2 => "swap2",
+            Opcode::DupX1 => "dupx1",
+            Opcode::DupX2 => "dupx2",
+            Opcode::FCmpT => "fcmpt",
+            Opcode::DCmpT => "dcmpt",
+            Opcode::FuncRef => "funcref",
+            Opcode::InvokeDynamic => "invokedynamic",
+            Opcode::TailInvoke => "tailinvoke",
+            Opcode::Throw => "throw",
+            Opcode::TryEnter => "tryenter",
+            Opcode::TryLeave => "tryleave",
+            Opcode::IRet => "iret",
+            Opcode::LRet => "lret",
+            Opcode::GotoW => "gotow",
+            Opcode::IfW => "ifw",
+            Opcode::IfNotW => "ifnotw",
+            Opcode::IfICmpEq => "ificmpeq",
+            Opcode::IfICmpNe => "ificmpne",
+            Opcode::IfICmpLt => "ificmplt",
+            Opcode::IfICmpGe => "ificmpge",
+            Opcode::IfICmpGt => "ificmpgt",
+            Opcode::IfICmpLe => "ificmple",
+            Opcode::IfLCmpEq => "iflcmpeq",
+            Opcode::IfLCmpNe => "iflcmpne",
+            Opcode::IfLCmpLt => "iflcmplt",
+            Opcode::IfLCmpGe => "iflcmpge",
+            Opcode::IfLCmpGt => "iflcmpgt",
+            Opcode::IfLCmpLe => "iflcmple",
+            Opcode::Ldc => "ldc",
+            Opcode::Ldc2 => "ldc2",
+            Opcode::LdStr => "ldstr",
+            Opcode::IMin => "imin",
+            Opcode::IMax => "imax",
+            Opcode::LMin => "lmin",
+            Opcode::LMax => "lmax",
+            Opcode::IAbs => "iabs",
+            Opcode::LAbs => "labs",
+            Opcode::IRotl => "irotl",
+            Opcode::IRotr => "irotr",
+            Opcode::LRotl => "lrotl",
+            Opcode::LRotr => "lrotr",
+            Opcode::IPopcnt => "ipopcnt",
+            Opcode::LPopcnt => "lpopcnt",
+            Opcode::IClz => "iclz",
+            Opcode::LClz => "lclz",
+            Opcode::ICtz => "ictz",
+            Opcode::LCtz => "lctz",
+            Opcode::ArrayCopy => "arraycopy",
+            Opcode::BAFill => "bafill",
+            Opcode::SAFill => "safill",
+            Opcode::IAFill => "iafill",
+            Opcode::LAFill => "lafill",
+            Opcode::I2F => "i2f",
+            Opcode::F2I => "f2i",
+            Opcode::L2D => "l2d",
+            Opcode::D2L => "d2l",
+            Opcode::FCmpL => "fcmpl",
+            Opcode::FCmpG => "fcmpg",
+            Opcode::DCmpL => "dcmpl",
+            Opcode::DCmpG => "dcmpg",
+            Opcode::IAddSat => "iaddsat",
+            Opcode::LAddSat => "laddsat",
+            Opcode::ISubSat => "isubsat",
+            Opcode::LSubSat => "lsubsat",
+            Opcode::IMulSat => "imulsat",
+            Opcode::LMulSat => "lmulsat",
+            Opcode::IALoadAtomic => "ialoadatomic",
+            Opcode::LALoadAtomic => "laloadatomic",
+            Opcode::IAStoreAtomic => "iastoreatomic",
+            Opcode::LAStoreAtomic => "lastoreatomic",
+            Opcode::IACas => "iacas",
+            Opcode::LACas => "lacas",
+            Opcode::IAFetchAdd => "iafetchadd",
+            Opcode::LAFetchAdd => "lafetchadd",
+            Opcode::GLoad => "gload",
+            Opcode::GLoad2 => "gload2",
+            Opcode::GStore => "gstore",
+            Opcode::GStore2 => "gstore2",
+            Opcode::GetField => "getfield",
+            Opcode::PutField => "putfield",
+            Opcode::New => "new",
+            Opcode::ICmp => "icmp",
+            Opcode::LCmp => "lcmp",
+            Opcode::QPush => "qpush",
+            Opcode::QAdd => "qadd",
+            Opcode::QSub => "qsub",
+            Opcode::QMul => "qmul",
+            Opcode::QDiv => "qdiv",
+            Opcode::IVAdd => "ivadd",
+            Opcode::LVAdd => "lvadd",
+            Opcode::FVAdd => "fvadd",
+            Opcode::DVAdd => "dvadd",
+            Opcode::IVMul => "ivmul",
+            Opcode::LVMul => "lvmul",
+            Opcode::FVMul => "fvmul",
+            Opcode::DVMul => "dvmul",
+            Opcode::IVEq => "iveq",
+            Opcode::LVEq => "lveq",
+            Opcode::MakeClosure => "makeclosure",
+            Opcode::InvokeClosure => "invokeclosure",
+            Opcode::CanarySet => "canaryset",
+            Opcode::CanaryCheck => "canarycheck",
+            Opcode::InvokeVirtual => "invokevirtual",
+            Opcode::Intern => "intern",
+            Opcode::LdInterned => "ldinterned",
         };
 
         return write!(f, "{}", s);
@@ -182,25 +516,555 @@ impl Into<u8> for Opcode {
     }
 }
 
+// spec: 0x00 - 0x7f は組み込みコール用に予約
+pub const HOST_CALL_NUMBER_START: u8 = 0x80;
+
+// note: 埋め込み側が定義するコール; 引数はスタック上のバイト列のまま渡され、戻り値のバイト列がプッシュされる
+// spec: インポート表を持つモジュールからは name で、持たないモジュールからは number で呼び出される
+#[derive(Clone)]
+pub struct HostCall {
+    pub name: String,
+    pub number: u8,
+    pub arg_size: usize,
+    pub ret_size: usize,
+    pub handler: fn(&mut HostContext, &[u8]) -> Option<Vec<u8>>,
+}
+
+// note: 定数領域 (配列と同じ形式; 実行終了まで解放されない) と内容からハンドルへの対応
+// spec: 確保したサイズは配列と共通の heap_size に加算し、max_heap_size を超える場合は確保しない (登録元によらず同じ扱い)
+struct ConstantArea {
+    ptrs: Vec<*mut c_void>,
+    handles: HashMap<Vec<u8>, u32>,
+    max_heap_size: Option<usize>,
+}
+
+impl ConstantArea {
+    fn new(max_heap_size: Option<usize>) -> ConstantArea {
+        return ConstantArea {
+            ptrs: Vec::new(),
+            handles: HashMap::new(),
+            max_heap_size: max_heap_size,
+        };
+    }
+
+    // note: 内容の重複によらず新しいハンドルを割り当てる (内容からの対応は最初のハンドルのまま)
+    // note: max_heap_size を超えるか確保に失敗した場合は None
+    unsafe fn push(&mut self, bytes: &[u8], heap_size: &mut usize) -> Option<u32> {
+        let alloc_size = size_of::<usize>().saturating_add(bytes.len());
+
+        if let Some(max_heap_size) = self.max_heap_size {
+            if heap_size.saturating_add(alloc_size) > max_heap_size {
+                return None;
+            }
+        }
+
+        let const_ptr = malloc(alloc_size);
+
+        if const_ptr.is_null() {
+            return None;
+        }
+
+        *heap_size += alloc_size;
+        *(const_ptr as *mut usize) = bytes.len();
+        copy_nonoverlapping(bytes.as_ptr(), (const_ptr as *mut usize).add(1) as *mut u8, bytes.len());
+
+        let handle = self.ptrs.len() as u32;
+        self.ptrs.push(const_ptr);
+        self.handles.entry(bytes.to_vec()).or_insert(handle);
+        return Some(handle);
+    }
+
+    // note: 同じ内容が登録済みであればそのハンドルを返す (新たに確保しないため heap_size は変わらない)
+    unsafe fn intern(&mut self, bytes: &[u8], heap_size: &mut usize) -> Option<u32> {
+        return match self.handles.get(bytes) {
+            Some(v) => Some(*v),
+            None => self.push(bytes, heap_size),
+        };
+    }
+
+    fn get(&self, handle: u32) -> Option<*mut c_void> {
+        return self.ptrs.get(handle as usize).copied();
+    }
+
+    fn contains(&self, ptr: *mut c_void) -> bool {
+        return self.ptrs.contains(&ptr);
+    }
+}
+
+// note: ホストコールのハンドラから参照できる実行中のインタプリタの状態
+pub struct HostContext<'a> {
+    constants: &'a mut ConstantArea,
+    heap_size: &'a mut usize,
+    user_data: Option<&'a mut dyn Any>,
+}
+
+impl<'a> HostContext<'a> {
+    // note: 定数を登録してハンドルを返す (バイトコード側で LdInterned により参照)
+    // note: 新たな確保が max_heap_size を超える場合は None
+    pub fn intern(&mut self, bytes: &[u8]) -> Option<u32> {
+        return unsafe { self.constants.intern(bytes, self.heap_size) };
+    }
+
+    // note: ハンドルに対応する定数の内容 (Intern や register_constant で登録されたもの)
+    pub fn constant(&self, handle: u32) -> Option<&[u8]> {
+        return self.constants.get(handle).map(|const_ptr| unsafe {
+            std::slice::from_raw_parts((const_ptr as *mut usize).add(1) as *const u8, *(const_ptr as *mut usize))
+        });
+    }
+
+    // note: 実行時に埋め込み側から渡された値 (渡されていないか型が異なれば None)
+    pub fn user_data<T: Any>(&mut self) -> Option<&mut T> {
+        return self.user_data.as_deref_mut().and_then(|user_data| user_data.downcast_mut::<T>());
+    }
+}
+
+// note: 入出力コールがエラーまたは途中までの処理で終わった場合の動作
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IoErrorPolicy {
+    // note: 割り込み時と書き込みが途中で終わった場合は再試行し、それ以外のエラーは IoFailed で終了
+    Retry,
+    // note: エラーと途中までの書き込みを IoFailed で終了
+    Trap,
+    // note: 終了せず、エラー時は処理したバイト数の代わりに最大値をプッシュしてバイトコード側に任せる
+    ReturnCount,
+}
+
+// note: 浮動小数点数から整数への変換 (F2I, D2L) で値が表現できない場合の動作
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatToIntPolicy {
+    // note: NaN は InvalidFloatOperation、範囲外は ArithmeticOverflow で終了
+    Trap,
+    // note: 範囲外は最小値または最大値に飽和し、NaN は 0 に変換
+    Saturate,
+}
+
+// note: 制限の度合いごとの設定の組み合わせ
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SandboxProfile {
+    // note: 入出力なし (計算用の組み込みコールのみ), 小さいスタック, 実行全体の命令数制限あり
+    Strict,
+    // note: 標準入出力を含む組み込みコールのみ (ホストコール不可), 実行全体の命令数制限あり
+    Standard,
+    // note: 制限なし (ファイルコールも許可)
+    Trusted,
+}
+
+#[derive(Clone)]
+pub struct InterpreterConfig {
+    // note: フレーム内で確保され、エスケープしていない配列を Ret 時に自動解放
+    auto_drop_arrays: bool,
+    // note: スタック全体のダンプの代わりに命令ごとのプッシュ/ポップのみを出力
+    stack_delta_trace: bool,
+    // note: NaN でない入力から NaN を生成する浮動小数点演算を InvalidFloatOperation で終了 (既定の false では IEEE 754 に従い NaN を伝播; 入力の NaN は常に伝播)
+    trap_on_nan: bool,
+    // note: 整数演算 (calc! による算術演算, 符号反転, 絶対値, Inc) のオーバーフローを ArithmeticOverflow で終了 (false の場合はラップアラウンド)
+    trap_on_overflow: bool,
+    // note: 配列への 8/16 ビット書き込みで値が収まらない場合に NarrowingOverflow で終了 (false の場合は切り捨て)
+    checked_narrowing: bool,
+    // note: 1 回の関数呼び出し内で実行できる命令数の上限 (呼び出し先での実行分は含まない)
+    max_call_instructions: Option<usize>,
+    // note: 実行全体で実行できる命令数の上限 (超えた場合は例外ハンドラによらず FuelExhausted で終了)
+    max_instructions: Option<usize>,
+    // note: スタック上の境界に揃っていない複数バイトアクセスを pc と共に出力
+    audit_alignment: bool,
+    // note: 保存済み bp とリターンアドレスをスタックではなく専用のリターンスタックに置く
+    side_return_stack: bool,
+    // note: Call で呼び出し可能なホストコールの一覧
+    host_calls: Vec<HostCall>,
+    // note: 関数ごとのオペランドスタックの最大使用量を RunReport に記録
+    record_stack_depth: bool,
+    // note: 実行した命令の種類ごとの回数を RunReport に記録し、終了時に出力
+    record_opcode_histogram: bool,
+    // note: read/write コールの失敗時の動作
+    io_error_policy: IoErrorPolicy,
+    // note: F2I, D2L で値が表現できない場合の動作
+    float_to_int_policy: FloatToIntPolicy,
+    // note: 実行前に登録する定数 (インデックスがハンドル; 実行時の Intern でも同じ領域に追加される)
+    constants: Vec<Vec<u8>>,
+    // note: スタック領域のバイトサイズ
+    stack_size: usize,
+    // note: 実行中に確保する配列と Intern による定数の合計バイトサイズ (サイズヘッダを含む) の上限 (超えた場合は HeapLimitExceeded で終了)
+    max_heap_size: Option<usize>,
+    // note: プログラム全体で呼び出し可能なコール番号 (None は制限なし; 関数ごとの許可リストとの共通部分が有効)
+    allowed_calls: Option<Vec<u8>>,
+    // note: ファイルコール (0x20 - 0x24) を許可 (false の場合は CallNotPermitted で終了)
+    file_calls: bool,
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> InterpreterConfig {
+        return InterpreterConfig::new();
+    }
+}
+
+impl InterpreterConfig {
+    pub fn new() -> InterpreterConfig {
+        return InterpreterConfig {
+            auto_drop_arrays: false,
+            stack_delta_trace: false,
+            trap_on_nan: false,
+            trap_on_overflow: true,
+            checked_narrowing: false,
+            max_call_instructions: None,
+            max_instructions: None,
+            audit_alignment: false,
+            side_return_stack: false,
+            host_calls: Vec::new(),
+            record_stack_depth: false,
+            record_opcode_histogram: false,
+            io_error_policy: IoErrorPolicy::Retry,
+            float_to_int_policy: FloatToIntPolicy::Trap,
+            constants: Vec::new(),
+            stack_size: 1024,
+            max_heap_size: None,
+            allowed_calls: None,
+            file_calls: false,
+        };
+    }
+
+    // spec: 0x00, 0x01 は標準入出力; 0x02 - 0x1f は入出力を伴わない組み込みコール
+    pub fn from_profile(profile: SandboxProfile) -> InterpreterConfig {
+        let mut config = InterpreterConfig::new();
+
+        match profile {
+            SandboxProfile::Strict => {
+                config.stack_size = 1024;
+                config.max_heap_size = Some(64 * 1024);
+                config.max_instructions = Some(1_000_000);
+                config.allowed_calls = Some((0x02..=0x1f).collect());
+                config.checked_narrowing = true;
+            },
+            SandboxProfile::Standard => {
+                config.stack_size = 16 * 1024;
+                config.max_heap_size = Some(16 * 1024 * 1024);
+                config.max_instructions = Some(100_000_000);
+                config.allowed_calls = Some((0x00..=0x1f).collect());
+            },
+            SandboxProfile::Trusted => {
+                config.stack_size = 1024 * 1024;
+                config.file_calls = true;
+            },
+        }
+
+        return config;
+    }
+
+    pub fn with_auto_drop_arrays(mut self, auto_drop_arrays: bool) -> InterpreterConfig {
+        self.auto_drop_arrays = auto_drop_arrays;
+        return self;
+    }
+
+    pub fn with_stack_delta_trace(mut self, stack_delta_trace: bool) -> InterpreterConfig {
+        self.stack_delta_trace = stack_delta_trace;
+        return self;
+    }
+
+    pub fn with_trap_on_nan(mut self, trap_on_nan: bool) -> InterpreterConfig {
+        self.trap_on_nan = trap_on_nan;
+        return self;
+    }
+
+    pub fn with_trap_on_overflow(mut self, trap_on_overflow: bool) -> InterpreterConfig {
+        self.trap_on_overflow = trap_on_overflow;
+        return self;
+    }
+
+    pub fn with_checked_narrowing(mut self, checked_narrowing: bool) -> InterpreterConfig {
+        self.checked_narrowing = checked_narrowing;
+        return self;
+    }
+
+    // spec: エントリポイントのフレームが収まらないサイズは実行前の検査 (validate_stack_size) でエラー
+    pub fn with_stack_size(mut self, stack_size: usize) -> InterpreterConfig {
+        self.stack_size = stack_size;
+        return self;
+    }
+
+    pub fn with_max_heap_size(mut self, max_heap_size: Option<usize>) -> InterpreterConfig {
+        self.max_heap_size = max_heap_size;
+        return self;
+    }
+
+    pub fn with_max_call_instructions(mut self, max_call_instructions: Option<usize>) -> InterpreterConfig {
+        self.max_call_instructions = max_call_instructions;
+        return self;
+    }
+
+    pub fn with_max_instructions(mut self, max_instructions: Option<usize>) -> InterpreterConfig {
+        self.max_instructions = max_instructions;
+        return self;
+    }
+
+    pub fn with_audit_alignment(mut self, audit_alignment: bool) -> InterpreterConfig {
+        self.audit_alignment = audit_alignment;
+        return self;
+    }
+
+    pub fn with_side_return_stack(mut self, side_return_stack: bool) -> InterpreterConfig {
+        self.side_return_stack = side_return_stack;
+        return self;
+    }
+
+    pub fn with_record_stack_depth(mut self, record_stack_depth: bool) -> InterpreterConfig {
+        self.record_stack_depth = record_stack_depth;
+        return self;
+    }
+
+    pub fn with_record_opcode_histogram(mut self, record_opcode_histogram: bool) -> InterpreterConfig {
+        self.record_opcode_histogram = record_opcode_histogram;
+        return self;
+    }
+
+    pub fn with_io_error_policy(mut self, io_error_policy: IoErrorPolicy) -> InterpreterConfig {
+        self.io_error_policy = io_error_policy;
+        return self;
+    }
+
+    pub fn with_float_to_int_policy(mut self, float_to_int_policy: FloatToIntPolicy) -> InterpreterConfig {
+        self.float_to_int_policy = float_to_int_policy;
+        return self;
+    }
+
+    pub fn with_allowed_calls(mut self, allowed_calls: Option<Vec<u8>>) -> InterpreterConfig {
+        self.allowed_calls = allowed_calls;
+        return self;
+    }
+
+    pub fn with_file_calls(mut self, file_calls: bool) -> InterpreterConfig {
+        self.file_calls = file_calls;
+        return self;
+    }
+
+    // note: 予約済み番号の使用や登録済みの番号・名前との重複があれば登録せずにホストコール名を返す
+    pub fn register_host_call(&mut self, host_call: HostCall) -> Result<(), String> {
+        if host_call.number < HOST_CALL_NUMBER_START {
+            return Err(host_call.name);
+        }
+
+        if self.host_calls.iter().any(|each_call| each_call.number == host_call.number || each_call.name == host_call.name) {
+            return Err(host_call.name);
+        }
+
+        self.host_calls.push(host_call);
+        return Ok(());
+    }
+
+    // note: 定数を登録してハンドルを返す (同じ内容が登録済みであればそのハンドル)
+    // spec: ホストコールは HostContext から定数を参照・登録でき、ハンドルを戻り値として返してバイトコード側で LdInterned により参照する
+    pub fn register_constant(&mut self, bytes: Vec<u8>) -> u32 {
+        if let Some(handle) = self.constants.iter().position(|each_const| *each_const == bytes) {
+            return handle as u32;
+        }
+
+        self.constants.push(bytes);
+        return (self.constants.len() - 1) as u32;
+    }
+
+    // note: インポート表の各要素に対応するホストコールのインデックス (インポート表がなければ None)
+    // spec: 名前が登録されていないか引数・戻り値のサイズが異なる要素はエラーとして diagnostics に追加し、インデックスを None とする
+    pub fn resolve_imports(&self, imports: Option<&[Import]>, diagnostics: &mut LoadDiagnostics) -> Option<Vec<Option<usize>>> {
+        let imports = imports?;
+        let mut call_indexes = Vec::<Option<usize>>::new();
+
+        for each_import in imports {
+            let call_i = match self.host_calls.iter().position(|each_call| each_call.name == each_import.name) {
+                Some(v) => v,
+                None => {
+                    diagnostics.push(DiagnosticLevel::Error, each_import.offset, "import name", "registered host call name".to_string(), format!("'{}'", each_import.name));
+                    call_indexes.push(None);
+                    continue;
+                },
+            };
+
+            let host_call = &self.host_calls[call_i];
+
+            if host_call.arg_size != each_import.arg_size || host_call.ret_size != each_import.ret_size {
+                diagnostics.push(DiagnosticLevel::Error, each_import.offset, "import signature", format!("{} byte args / {} byte rets", host_call.arg_size, host_call.ret_size), format!("{} byte args / {} byte rets ('{}')", each_import.arg_size, each_import.ret_size, each_import.name));
+                call_indexes.push(None);
+                continue;
+            }
+
+            call_indexes.push(Some(call_i));
+        }
+
+        return Some(call_indexes);
+    }
+
+    // note: エントリポイントのフレーム (保存済み bp とリターンアドレス) が収まらなければ理由を返す
+    pub fn validate_stack_size(&self) -> Result<(), String> {
+        if self.stack_size < size_of::<usize>() * 2 {
+            return Err(format!("stack size {} is smaller than the entry frame ({} bytes)", self.stack_size, size_of::<usize>() * 2));
+        }
+
+        return Ok(());
+    }
+
+    // note: 予約済み番号の使用や番号・名前の重複があれば該当するホストコール名を返す
+    pub fn validate_host_calls(&self) -> Result<(), String> {
+        for (i, each_call) in self.host_calls.iter().enumerate() {
+            if each_call.number < HOST_CALL_NUMBER_START {
+                return Err(each_call.name.clone());
+            }
+
+            if self.host_calls[..i].iter().any(|prev_call| prev_call.number == each_call.number || prev_call.name == each_call.name) {
+                return Err(each_call.name.clone());
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+// note: 実行後に埋め込み側へ返す計測結果
+#[derive(Clone, Default)]
+pub struct RunReport {
+    // note: 関数プールインデックスごとのオペランドスタックの最大使用量 (バイト単位; 変数テーブルを除く)
+    max_operand_depths: HashMap<usize, usize>,
+    // note: オペコード名ごとの実行回数 (record_opcode_histogram 有効時のみ)
+    opcode_counts: HashMap<String, usize>,
+    // note: 最後に検出したプール要素の不整合 (例外として捕捉された場合も含む)
+    descriptor_error: Option<DescriptorError>,
+}
+
+impl RunReport {
+    pub fn new() -> RunReport {
+        return RunReport {
+            max_operand_depths: HashMap::new(),
+            opcode_counts: HashMap::new(),
+            descriptor_error: None,
+        };
+    }
+
+    pub fn max_operand_depths(&self) -> &HashMap<usize, usize> {
+        return &self.max_operand_depths;
+    }
+
+    pub fn opcode_counts(&self) -> &HashMap<String, usize> {
+        return &self.opcode_counts;
+    }
+
+    pub fn descriptor_error(&self) -> Option<&DescriptorError> {
+        return self.descriptor_error.as_ref();
+    }
+}
+
+// note: InvalidFunctionDescriptor / InvalidLayoutDescriptor の原因となったプール要素と項目
+#[derive(Clone, Debug)]
+pub struct DescriptorError {
+    pub pool_index: usize,
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Display for DescriptorError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        return write!(f, "pool index 0x{:0x} ({}): expected {}, actual {}", self.pool_index, self.field, self.expected, self.actual);
+    }
+}
+
+// note: 呼び出し中の各フレームについてホスト側で保持する情報
+struct FrameInfo {
+    // note: 関数プールインデックス (エントリポイントは 0)
+    pool_i: usize,
+    // note: 変数テーブルのバイトサイズ
+    table_size: usize,
+    // note: オペランドスタックの最大使用量 (record_stack_depth 有効時のみ更新)
+    max_operand_depth: usize,
+    // note: フレーム内で実行した命令数
+    inst_count: usize,
+    // note: 実行可能なコール番号 (None は制限なし)
+    call_allow_list: Option<Vec<u8>>,
+    // note: 各変数の変数テーブル先頭からのオフセットと、末尾にテーブルサイズ (None は 4 バイト単位)
+    slot_offsets: Option<Vec<usize>>,
+    // note: CanarySet で配置したカナリアの bp からのオフセットと値
+    canary: Option<(usize, u64)>,
+    // note: Ret 時に呼び出し元へ残す値の要素数 (4 バイト単位)
+    ret_len: usize,
+    // note: 各変数の種別 (DESCRIPTOR_FLAG_SLOT_KINDS 指定時のみ)
+    slot_kinds: Option<Vec<SlotKind>>,
+}
+
+impl FrameInfo {
+    fn new(pool_i: usize, table_size: usize, call_allow_list: Option<Vec<u8>>, slot_offsets: Option<Vec<usize>>, ret_len: usize, slot_kinds: Option<Vec<SlotKind>>) -> FrameInfo {
+        return FrameInfo {
+            pool_i: pool_i,
+            table_size: table_size,
+            max_operand_depth: 0,
+            inst_count: 0,
+            call_allow_list: call_allow_list,
+            slot_offsets: slot_offsets,
+            canary: None,
+            ret_len: ret_len,
+            slot_kinds: slot_kinds,
+        };
+    }
+}
+
+// note: TryEnter で登録された例外ハンドラと、捕捉時に復元する状態
+struct ExceptionHandler {
+    addr: usize,
+    sp: usize,
+    bp: usize,
+    call_depth: usize,
+}
+
 pub struct Interpreter {}
 
 impl Interpreter {
-    pub unsafe fn launch(bytecode_bytes: Vec<u8>) -> ExitStatus {
-        let bytecode = Bytecode::new(bytecode_bytes);
+    pub unsafe fn launch(bytecode_bytes: Vec<u8>, config: &InterpreterConfig) -> ExitStatus {
+        return Interpreter::launch_with_report(bytecode_bytes, config, None).0;
+    }
+
+    // note: user_data はホストコールのハンドラから HostContext::user_data で参照される
+    pub unsafe fn launch_with_report(bytecode_bytes: Vec<u8>, config: &InterpreterConfig, user_data: Option<&mut dyn Any>) -> (ExitStatus, RunReport) {
+        let (bytecode, mut diagnostics) = Bytecode::new(bytecode_bytes);
+        let import_calls = config.resolve_imports(bytecode.imports(), &mut diagnostics);
+
+        #[cfg(feature = "trace")]
+        for each_diagnostic in &diagnostics.items {
+            match each_diagnostic.level {
+                DiagnosticLevel::Error => println!("{}", each_diagnostic.to_string().on_red()),
+                DiagnosticLevel::Warning => println!("{}", each_diagnostic.to_string().yellow()),
+            }
+
+            // note: 該当オフセットを含む 16 バイトを表示
+            let line_begin = each_diagnostic.offset / 16 * 16;
+            let line_end = (line_begin + 16).min(bytecode.len());
+
+            if let Some(line_bytes) = bytecode.get_bytes(BytecodeRange::new(line_begin, line_end - line_begin)) {
+                println!("{}", format!("0x{:04x}  {}", line_begin, Bytecode::bytes_to_string(&line_bytes)).bright_black());
+            }
+        }
+
+        if diagnostics.has_errors() {
+            return (ExitStatus::InvalidBytecode, RunReport::new());
+        }
 
-        if *HEADER_SIZE > bytecode.len() {
-            panic!("{}", "invalid header size".on_red());
+        // note: register_host_call で検査済みだが、実行前にも念のため検査
+        if let Err(_call_name) = config.validate_host_calls() {
+            #[cfg(feature = "trace")]
+            println!("{}", format!("invalid host call '{}'", _call_name).on_red());
+            return (ExitStatus::InvalidHostCall, RunReport::new());
         }
 
-        if !bytecode.match_bytes(HeaderItem::MagicNumber.get_bytecode_range(), &MAGIC_NUMBER.to_vec()) {
-            panic!("{}", "invalid magic number".on_red());
+        // note: with_stack_size や from_profile で設定されたサイズを実行前に検査
+        if let Err(_message) = config.validate_stack_size() {
+            #[cfg(feature = "trace")]
+            println!("{}", _message.on_red());
+            return (ExitStatus::StackOverflow, RunReport::new());
         }
 
+        #[cfg(feature = "trace")]
         bytecode.print();
-        return Interpreter::run(&mut *bytecode.into_vec());
+        let vtables = bytecode.vtables().cloned().unwrap_or_default();
+        let indirect_call_targets = bytecode.indirect_call_targets().clone();
+        return Interpreter::run(&mut *bytecode.into_vec(), config, import_calls, &vtables, &indirect_call_targets, user_data);
     }
 
-    unsafe fn run(bytecode_bytes: &mut Vec<u8>) -> ExitStatus {
+    // note: import_calls はインポート表の各要素に対応するホストコールのインデックス (resolve_imports の結果)
+    // note: vtables と indirect_call_targets は読み込み時に検査済みの vtable と間接呼び出しの呼び出し先
+    unsafe fn run(bytecode_bytes: &mut Vec<u8>, config: &InterpreterConfig, import_calls: Option<Vec<Option<usize>>>, vtables: &HashSet<usize>, indirect_call_targets: &HashSet<usize>, mut user_data: Option<&mut dyn Any>) -> (ExitStatus, RunReport) {
         let mut is_init_succeeded = true;
         // note: Exit Status
         let mut es = ExitStatus::Success as u32;
@@ -220,7 +1084,7 @@ impl Interpreter {
             es = ExitStatus::BytecodeAccessViolation as u32;
         }
 
-        let max_stack_size = 1024usize;
+        let max_stack_size = config.stack_size;
         let mut stack_ptr = malloc(max_stack_size) as *mut c_void;
 
         // note: Stack Pointer
@@ -232,6 +1096,63 @@ impl Interpreter {
         // note: Pool Pointer
         let mut pp = pool_offset;
 
+        // note: 実行中の命令の先頭位置
+        #[cfg(feature = "trace")]
+        let mut opcode_pc = entry_point_pc;
+
+        // note: フレーム先頭に置かれる保存済み bp とリターンアドレスのサイズ
+        let frame_header_size = if config.side_return_stack { 0 } else { size_of::<usize>() * 2 };
+        // note: side_return_stack 有効時の (保存済み bp, リターンアドレス)
+        let mut return_stack = Vec::<(usize, usize)>::new();
+
+        // note: 自動解放の対象となる配列 (所有フレームの深さ, 配列ポインタ)
+        // spec: フレームの深さは frame_infos の要素数 (side_return_stack 有効時は呼び出し先と呼び出し元の bp が一致しうるため bp は使わない)
+        let mut owned_arrs = Vec::<(usize, *mut c_void)>::new();
+        // note: 実行中に確保した配列と定数の合計バイトサイズ (max_heap_size の判定用)
+        let mut heap_size = 0usize;
+
+        // note: グローバル変数領域 (ヘッダで指定された要素数 × 4 バイト; 0 で初期化)
+        let global_len_range = HeaderItem::GlobalLen.get_bytecode_range();
+        let global_len = u16::from_ne_bytes([bytecode_bytes[global_len_range.begin], bytecode_bytes[global_len_range.begin + 1]]) as usize;
+        let mut globals = vec![0u32; global_len];
+
+        // note: 関数プール要素の拡張フィールドの有無 (ヘッダで指定)
+        let descriptor_flags = bytecode_bytes[HeaderItem::DescriptorFlags.get_bytecode_range().begin];
+        let has_call_allow_lists = descriptor_flags & DESCRIPTOR_FLAG_CALL_ALLOW_LIST != 0;
+        let has_slot_kinds = descriptor_flags & DESCRIPTOR_FLAG_SLOT_KINDS != 0;
+        let has_ret_len = descriptor_flags & DESCRIPTOR_FLAG_RET_LEN != 0;
+
+        // note: 各フレームの情報 (エントリポイントのコール番号の制限は allowed_calls に従う)
+        let mut frame_infos = vec![FrameInfo::new(0, 0, config.allowed_calls.clone(), None, 0, None)];
+
+        let mut report = RunReport::new();
+
+        // note: 実行全体での命令数 (max_instructions 指定時のみ更新)
+        let mut total_inst_count = 0usize;
+
+        // note: オペコードの値ごとの実行回数
+        let mut opcode_counts = [0usize; 256];
+
+        // note: カナリア生成用の乱数状態 (xorshift64; 起動時刻とスタックのアドレスから初期化)
+        let mut rng_state = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |v| v.as_nanos() as u64) ^ stack_ptr as u64 | 1;
+
+        // note: 定数領域 (登録順のインデックスがハンドル)
+        let mut constants = ConstantArea::new(config.max_heap_size);
+
+        for each_const in &config.constants {
+            if constants.push(each_const, &mut heap_size).is_none() {
+                is_init_succeeded = false;
+                es = ExitStatus::HeapLimitExceeded as u32;
+                break;
+            }
+        }
+
+        // note: ファイルコールで開いたファイルのディスクリプタ (インデックスがハンドル; 閉じたものは None)
+        let mut file_handles = Vec::<Option<c_int>>::new();
+
+        // note: 登録中の例外ハンドラ
+        let mut exception_handlers = Vec::<ExceptionHandler>::new();
+
         // note: 'operator ブロック外での終了処理
         // fix: 処理が中断されない
         macro_rules! exit {
@@ -278,6 +1199,14 @@ impl Interpreter {
             };
         }
 
+        macro_rules! audit_alignment {
+            ($ptr:expr, $ty:ty) => {
+                if config.audit_alignment && size_of::<$ty>() > 1 && !($ptr as usize).is_multiple_of(size_of::<$ty>()) {
+                    trace!("{}", format!("[misaligned {} byte access at 0x{:0x} / stack address 0x{:0x}]", size_of::<$ty>(), opcode_pc, $ptr as usize).yellow());
+                }
+            };
+        }
+
         macro_rules! push {
             ($ptr:expr, $curr_pos:expr, $ty:ty, $value:expr, $size:expr, $err_status:ident) => {
                 {
@@ -285,20 +1214,28 @@ impl Interpreter {
 
                     if $curr_pos + value_size > $size {
                         exit!($err_status);
-                    }
-
-                    let tmp_ptr = $ptr as *mut $ty;
-                    *tmp_ptr = $value;
+                    } else {
+                        // note: スタックはバイト単位で詰めて配置されるため境界に揃っているとは限らない
+                        let tmp_ptr = $ptr as *mut $ty;
+                        std::ptr::write_unaligned(tmp_ptr, $value);
 
-                    $curr_pos += value_size;
-                    $ptr = $ptr.add(value_size);
+                        $curr_pos += value_size;
+                        $ptr = $ptr.add(value_size);
+                    }
                 }
             };
         }
 
         macro_rules! stack_push {
             ($ty:ty, $value:expr) => {
-                push!(stack_ptr, sp, $ty, $value, max_stack_size, StackOverflow)
+                {
+                    audit_alignment!(stack_ptr, $ty);
+                    push!(stack_ptr, sp, $ty, $value, max_stack_size, StackOverflow);
+
+                    if config.stack_delta_trace {
+                        trace!("{}", format!("push {}", raw_ptr_to_string!(stack_ptr.sub(size_of::<$ty>()), size_of::<$ty>())).bright_black());
+                    }
+                }
             };
 
             ($ty:ty, $value:expr, $len:expr) => {
@@ -317,93 +1254,362 @@ impl Interpreter {
             };
         }
 
+        // note: 先頭にバイトサイズを持つ配列を確保
+        macro_rules! alloc_arr {
+            ($arr_len:expr) => {
+                {
+                    let arr_len = $arr_len;
+                    let alloc_size = size_of::<usize>().saturating_add(arr_len);
+
+                    if let Some(max_heap_size) = config.max_heap_size {
+                        if heap_size.saturating_add(alloc_size) > max_heap_size {
+                            exit!(HeapLimitExceeded);
+                        }
+                    }
+
+                    let arr_ptr = malloc(alloc_size);
+
+                    if arr_ptr.is_null() {
+                        exit!(HeapLimitExceeded);
+                    }
+
+                    heap_size += alloc_size;
+                    *(arr_ptr as *mut usize) = arr_len;
+
+                    if config.auto_drop_arrays {
+                        owned_arrs.push((frame_infos.len(), arr_ptr));
+                    }
+
+                    arr_ptr
+                }
+            };
+        }
+
+        // note: alloc_arr! で確保した配列を解放
+        macro_rules! free_arr {
+            ($arr_ptr:expr) => {
+                {
+                    let arr_ptr = $arr_ptr;
+                    heap_size = heap_size.saturating_sub(size_of::<usize>() + *(arr_ptr as *mut usize));
+                    free(arr_ptr);
+                }
+            };
+        }
+
         macro_rules! stack_push_arr {
             ($ty:ty) => {
                 {
-                    // fix: 指定サイズ過大によるオーバーフロー
-                    let arr_len = next_prg!(usize) * size_of::<$ty>();
-                    let arr_ptr = malloc(size_of::<usize>() + arr_len);
-                    *(arr_ptr as *mut usize) = arr_len;
+                    // spec: バイトサイズが usize に収まらない要素数は確保できないサイズとして扱う
+                    let arr_size = match next_prg!(usize).checked_mul(size_of::<$ty>()) {
+                        Some(v) => v,
+                        None => exit!(HeapLimitExceeded),
+                    };
+
+                    let arr_ptr = alloc_arr!(arr_size);
                     stack_push!(*mut $ty, arr_ptr as *mut $ty);
                 }
             };
         }
 
-        macro_rules! pop {
-            ($ptr:expr, $curr_pos:expr, $ty:ty, $err_status:ident) => {
+        // spec: プール要素はバイト長 (usize) と文字列のバイト列
+        macro_rules! stack_push_str {
+            () => {
                 {
-                    let value_size = size_of::<$ty>();
+                    let pool_i = next_prg!(usize);
+                    jump_pool_to!(pool_i);
+                    let str_len = next_pool!(usize);
 
-                    if $curr_pos < value_size {
-                        exit!($err_status);
+                    if pp + str_len > bytecode_len {
+                        exit!(BytecodeAccessViolation);
                     }
 
-                    $curr_pos -= value_size;
-                    $ptr = $ptr.sub(value_size);
+                    let arr_ptr = alloc_arr!(str_len);
+                    copy_nonoverlapping(pool_ptr as *const u8, (arr_ptr as *mut usize).add(1) as *mut u8, str_len);
+                    stack_push!(*mut u8, arr_ptr as *mut u8);
 
-                    *($ptr as *mut $ty)
+                    trace!("{}", format!("[pool index 0x{:0x} / {} byte size]", pool_i, str_len).bright_green().dimmed());
+                    trace!();
                 }
             };
         }
 
-        // spec: リターンアドレス以前の領域にアクセス可能
-        macro_rules! unsafe_stack_pop {
-            ($ty:ty) => {
-                pop!(stack_ptr, sp, $ty, StackAccessViolation)
-            };
+        // spec: max_instructions 指定時に units 命令分の実行として計上し、上限を超えた場合は FuelExhausted で終了
+        // spec: 長さに比例する命令は処理の前に追加で計上 (ベクトル演算は 1 要素, 配列の埋め込み・コピーと多倍長整数は 8 バイトごとに 1 命令分)
+        macro_rules! charge_fuel {
+            ($units:expr) => {
+                if let Some(max_insts) = config.max_instructions {
+                    total_inst_count = total_inst_count.saturating_add($units);
 
-            ($ty:ty, $len:expr) => {
-                for _ in 0..$len {
-                    unsafe_stack_pop!($ty);
+                    if total_inst_count > max_insts {
+                        exit!(FuelExhausted);
+                    }
                 }
             };
         }
 
-        macro_rules! stack_pop {
-            ($ty:ty) => {
+        // spec: 多倍長整数は符号付き 2 の補数表現のリトルエンディアンのバイト配列 (空の配列は 0; LdStr でプールから読み込み可)
+        macro_rules! pop_big_int {
+            () => {
                 {
-                    // note: リターンアドレス以前の値にアクセスしないようチェック
-                    if sp < bp + size_of::<usize>() * 2 + size_of::<$ty>() {
-                        exit!(StackAccessViolation);
-                    }
-
-                    unsafe_stack_pop!($ty)
+                    let arr_ptr = stack_pop!(*mut usize);
+                    charge_fuel!((*arr_ptr).div_ceil(8));
+                    BigInt::from_signed_bytes_le(std::slice::from_raw_parts(arr_ptr.add(1) as *const u8, *arr_ptr))
                 }
             };
+        }
 
-            ($ty:ty, $len:expr) => {
-                for _ in 0..$len {
-                    stack_pop!($ty);
+        macro_rules! push_big_int {
+            ($value:expr) => {
+                {
+                    let bytes = $value.to_signed_bytes_le();
+                    let arr_ptr = alloc_arr!(bytes.len());
+                    copy_nonoverlapping(bytes.as_ptr(), (arr_ptr as *mut usize).add(1) as *mut u8, bytes.len());
+                    stack_push!(*mut u8, arr_ptr as *mut u8);
                 }
             };
         }
 
-        macro_rules! var_table_diff {
-            ($ty:ty, $var_i:expr) => {
+        // note: is_quadratic は乗除算 (両オペランドの語数の積を追加で計上)
+        macro_rules! big_int_calc {
+            ($op:tt, $is_quadratic:expr$(, $check_divide_by_zero:expr)?) => {
                 {
-                    // note: リターンアドレス以前の値にアクセスしないようチェック
-                    if sp < bp + size_of::<usize>() * 2 {
-                        exit!(StackAccessViolation);
-                    }
+                    let right_term = pop_big_int!();
+                    let left_term = pop_big_int!();
 
-                    let diff = sp - bp - size_of::<usize>() * 2;
+                    $(
+                        if $check_divide_by_zero && right_term.is_zero() {
+                            exit!(DivideByZero);
+                        }
+                    )?
 
-                    // note: スタックポインタ以降の値にアクセスしないようチェック
-                    if diff < size_of::<u32>() * $var_i as usize + size_of::<$ty>() {
-                        exit!(StackAccessViolation);
+                    if $is_quadratic {
+                        let left_words = (left_term.bits() as usize).div_ceil(64);
+                        let right_words = (right_term.bits() as usize).div_ceil(64);
+                        charge_fuel!(left_words.saturating_mul(right_words));
                     }
 
-                    diff - $var_i as usize * size_of::<u32>()
+                    push_big_int!(left_term $op right_term);
                 }
             };
         }
 
-        macro_rules! load {
-            ($ty:ty, $var_i:expr) => {
-                {
+        // note: 配列参照が別の場所に保存された場合は自動解放の対象から外す
+        // spec: 参照が 32 ビットずつ分けて保存される場合も検出できるよう、保存された 32 ビット語が参照の下位 32 ビットと一致すればエスケープとみなす (参照の復元には下位 32 ビットが必要なため)
+        // spec: 変数テーブルはフレームと共に破棄されるため、変数への保存はエスケープとみなさない
+        macro_rules! escape_word {
+            ($word:expr) => {
+                if config.auto_drop_arrays {
+                    let escaped_word = $word as u32;
+                    owned_arrs.retain(|(_, each_ptr)| *each_ptr as usize as u32 != escaped_word);
+                }
+            };
+        }
+
+        // note: 32 ビット以上の値を保存した場合のエスケープ判定 (64 ビット値は各 32 ビット語を検査)
+        macro_rules! escape_arr {
+            ($value:expr) => {
+                {
+                    let escaped_value = $value as u64;
+                    escape_word!(escaped_value);
+                    escape_word!(escaped_value >> 32);
+                }
+            };
+        }
+
+        // note: 入出力を行い、(処理したバイト数, 失敗したか) を返す
+        // spec: Retry では割り込み時に再試行し、$retry_partial が true の場合は途中までの処理の後も続きを再試行
+        macro_rules! io_call {
+            ($io_fn:ident, $fd:expr, $buf_ptr:expr, $len:expr, $retry_partial:expr) => {
+                {
+                    let buf_ptr = $buf_ptr as *mut u8;
+                    let len: usize = $len;
+                    let mut count = 0usize;
+                    let mut is_failed = false;
+
+                    while count < len {
+                        let result = $io_fn($fd, buf_ptr.add(count) as *mut c_void, len - count);
+
+                        if result < 0 {
+                            if config.io_error_policy == IoErrorPolicy::Retry && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                                continue;
+                            }
+
+                            is_failed = true;
+                            break;
+                        }
+
+                        count += result as usize;
+
+                        if result == 0 || !($retry_partial && config.io_error_policy == IoErrorPolicy::Retry) {
+                            break;
+                        }
+                    }
+
+                    (count, is_failed)
+                }
+            };
+        }
+
+        // spec: 書き込んだバイト数 (u32; ReturnCount でのエラー時は u32::MAX) を常にプッシュ
+        // spec: 方針は終了するかどうかのみを決める (スタックへの影響は方針によらない)
+        macro_rules! io_result {
+            ($count:expr, $is_failed:expr, $is_partial:expr) => {
+                {
+                    let is_trapped = match config.io_error_policy {
+                        IoErrorPolicy::Retry => $is_failed,
+                        IoErrorPolicy::Trap => $is_failed || $is_partial,
+                        IoErrorPolicy::ReturnCount => false,
+                    };
+
+                    if is_trapped {
+                        exit!(IoFailed);
+                    }
+
+                    stack_push!(u32, if $is_failed { u32::MAX } else { $count as u32 });
+                }
+            };
+        }
+
+        // spec: 結果の値は常にプッシュし、失敗時は ReturnCount では最大値をプッシュ, それ以外は IoFailed で終了
+        macro_rules! io_value {
+            ($ty:ty, $value:expr, $is_failed:expr) => {
+                if $is_failed {
+                    if config.io_error_policy != IoErrorPolicy::ReturnCount {
+                        exit!(IoFailed);
+                    }
+
+                    stack_push!($ty, <$ty>::MAX);
+                } else {
+                    stack_push!($ty, $value as $ty);
+                }
+            };
+        }
+
+        // note: ファイルハンドルに対応するディスクリプタ
+        macro_rules! file_fd {
+            ($handle:expr) => {
+                {
+                    let handle = $handle as usize;
+
+                    match file_handles.get(handle) {
+                        Some(Some(v)) => *v,
+                        _ => {
+                            trace!("{}", format!("[file handle {} is not open]", handle).red());
+                            trace!();
+                            exit!(IoFailed);
+                        },
+                    }
+                }
+            };
+        }
+
+        macro_rules! pop {
+            ($ptr:expr, $curr_pos:expr, $ty:ty, $err_status:ident) => {
+                {
+                    let value_size = size_of::<$ty>();
+
+                    if $curr_pos < value_size {
+                        exit!($err_status);
+                    }
+
+                    $curr_pos -= value_size;
+                    $ptr = $ptr.sub(value_size);
+
+                    std::ptr::read_unaligned($ptr as *mut $ty)
+                }
+            };
+        }
+
+        // spec: リターンアドレス以前の領域にアクセス可能
+        macro_rules! unsafe_stack_pop {
+            ($ty:ty) => {
+                {
+                    let value = pop!(stack_ptr, sp, $ty, StackAccessViolation);
+                    audit_alignment!(stack_ptr, $ty);
+
+                    if config.stack_delta_trace {
+                        trace!("{}", format!("pop {}", raw_ptr_to_string!(stack_ptr, size_of::<$ty>())).bright_black());
+                    }
+
+                    value
+                }
+            };
+
+            ($ty:ty, $len:expr) => {
+                {
+                    let pop_len = $len;
+
+                    for _ in 0..pop_len {
+                        let _ = pop!(stack_ptr, sp, $ty, StackAccessViolation);
+                    }
+
+                    if config.stack_delta_trace {
+                        trace!("{}", format!("pop {} bytes", pop_len * size_of::<$ty>()).bright_black());
+                    }
+                }
+            };
+        }
+
+        macro_rules! stack_pop {
+            ($ty:ty) => {
+                {
+                    // note: リターンアドレス以前の値にアクセスしないようチェック
+                    if sp < bp + frame_header_size + size_of::<$ty>() {
+                        exit!(StackAccessViolation);
+                    }
+
+                    unsafe_stack_pop!($ty)
+                }
+            };
+
+            ($ty:ty, $len:expr) => {
+                for _ in 0..$len {
+                    stack_pop!($ty);
+                }
+            };
+        }
+
+        macro_rules! var_table_diff {
+            ($ty:ty, $var_i:expr) => {
+                {
+                    // note: リターンアドレス以前の値にアクセスしないようチェック
+                    if sp < bp + frame_header_size {
+                        exit!(StackAccessViolation);
+                    }
+
+                    let diff = sp - bp - frame_header_size;
+
+                    // spec: DESCRIPTOR_FLAG_SLOT_KINDS 指定時は変数の幅と異なる幅でのアクセスを不可とする
+                    let slot_offset = match frame_infos.last().and_then(|each_info| each_info.slot_offsets.as_ref()) {
+                        Some(offsets) => {
+                            let var_i = $var_i as usize;
+
+                            if var_i + 1 >= offsets.len() || offsets[var_i + 1] - offsets[var_i] != size_of::<$ty>() {
+                                exit!(StackAccessViolation);
+                            }
+
+                            offsets[var_i]
+                        },
+                        None => $var_i as usize * size_of::<u32>(),
+                    };
+
+                    // note: スタックポインタ以降の値にアクセスしないようチェック
+                    if diff < slot_offset + size_of::<$ty>() {
+                        exit!(StackAccessViolation);
+                    }
+
+                    diff - slot_offset
+                }
+            };
+        }
+
+        macro_rules! load {
+            ($ty:ty, $var_i:expr) => {
+                {
                     let diff = var_table_diff!($ty, $var_i);
                     let value = stack_ptr.sub(diff) as *mut $ty;
-                    stack_push!($ty, *value);
+                    audit_alignment!(value, $ty);
+                    stack_push!($ty, std::ptr::read_unaligned(value));
                 }
             };
         }
@@ -423,8 +1629,8 @@ impl Interpreter {
                     let value = *(arr_top_ptr as *mut $ty).add(arr_i);
                     stack_push!($ty, value);
 
-                    println!("{}", format!("[index {} / {} byte size / value 0x{:0x}]", arr_i, arr_size, value).bright_green().dimmed());
-                    println!();
+                    trace!("{}", format!("[index {} / {} byte size / value 0x{:0x}]", arr_i, arr_size, value).bright_green().dimmed());
+                    trace!();
                 }
             };
         }
@@ -433,15 +1639,35 @@ impl Interpreter {
             ($ty:ty, $var_i:expr, $value:expr) => {
                 let diff = var_table_diff!($ty, $var_i);
                 let ptr = stack_ptr.sub(diff) as *mut $ty;
-                *ptr = $value
+                audit_alignment!(ptr, $ty);
+                std::ptr::write_unaligned(ptr, $value)
+            };
+        }
+
+        // spec: checked_narrowing 有効時はゼロ拡張・符号拡張のいずれかで元の値に戻る場合のみ許可
+        macro_rules! narrow {
+            ($value:expr, $ty:ty) => {
+                {
+                    let value = $value;
+
+                    if config.checked_narrowing && size_of::<$ty>() < size_of::<u32>() {
+                        let signed_value = value as u32 as i32 as i64;
+                        let signed_min = -((<$ty>::MAX as i64 + 1) / 2);
+
+                        if value as u64 > <$ty>::MAX as u64 && !(signed_min <= signed_value && signed_value < 0) {
+                            exit!(NarrowingOverflow);
+                        }
+                    }
+
+                    value as $ty
+                }
             };
         }
 
         macro_rules! store_arr {
             ($ty:ty, $pop_ty:ty) => {
                 {
-                    // fix: キャストでのオーバーフロー対処 (現在は数値が丸められてる)
-                    let value = stack_pop!($pop_ty) as $ty;
+                    let value = narrow!(stack_pop!($pop_ty), $ty);
                     let arr_i = stack_pop!(usize);
                     let arr_ptr = stack_pop!(*mut c_void);
                     let arr_size = *(arr_ptr as *mut usize);
@@ -451,11 +1677,231 @@ impl Interpreter {
                     }
 
                     let arr_top_ptr = (arr_ptr as *mut usize).add(1);
-                    let arr_elem_ptr = (arr_top_ptr as *mut $ty).add(arr_i) as *mut $ty;
+                    let arr_elem_ptr = (arr_top_ptr as *mut $ty).add(arr_i);
                     *arr_elem_ptr = value;
 
-                    println!("{}", format!("[index {} / {} byte size / change value to 0x{:0x}]", arr_i, arr_size, value).bright_green().dimmed());
-                    println!();
+                    if size_of::<$ty>() >= size_of::<u32>() {
+                        escape_arr!(value);
+                    }
+
+                    trace!("{}", format!("[index {} / {} byte size / change value to 0x{:0x}]", arr_i, arr_size, value).bright_green().dimmed());
+                    trace!();
+                }
+            };
+        }
+
+        // note: プール要素の不整合を計測結果に記録して終了
+        macro_rules! descriptor_error {
+            ($status_kind:ident, $pool_i:expr, $field:expr, $expected:expr, $actual:expr) => {
+                {
+                    let error = DescriptorError {
+                        pool_index: $pool_i,
+                        field: $field,
+                        expected: $expected,
+                        actual: $actual,
+                    };
+
+                    trace!("{}", format!("[{}]", error).red());
+                    trace!();
+                    report.descriptor_error = Some(error);
+                    exit!($status_kind);
+                }
+            };
+        }
+
+        // spec: 変数テーブルと同様に 4 バイト単位でインデックス指定
+        macro_rules! global_ptr {
+            ($ty:ty, $global_i:expr) => {
+                {
+                    let global_i = $global_i as usize;
+
+                    if global_i * size_of::<u32>() + size_of::<$ty>() > global_len * size_of::<u32>() {
+                        exit!(GlobalAccessViolation);
+                    }
+
+                    globals.as_mut_ptr().add(global_i) as *mut $ty
+                }
+            };
+        }
+
+        // note: レイアウトのプール要素からオブジェクトサイズとフィールドのオフセット・幅を取得
+        // spec: プール要素は オブジェクトサイズ u32, フィールド数 u16, 各フィールドの (オフセット u32, 幅 u8)
+        macro_rules! field_layout {
+            ($pool_i:expr, $field_i:expr) => {
+                {
+                    let pool_i = $pool_i;
+                    let field_i = $field_i as usize;
+                    jump_pool_to!(pool_i);
+                    let obj_size = next_pool!(u32) as usize;
+                    let field_len = next_pool!(u16) as usize;
+
+                    if field_i >= field_len {
+                        descriptor_error!(InvalidLayoutDescriptor, pool_i, "field_len", format!("more than {}", field_i), field_len.to_string());
+                    }
+
+                    jump_to!(pool_ptr, pp, pp + field_i * (size_of::<u32>() + size_of::<u8>()), bytecode_len, BytecodeAccessViolation);
+                    let offset = next_pool!(u32) as usize;
+                    let width = next_pool!(u8) as usize;
+
+                    if !matches!(width, 1 | 2 | 4 | 8) {
+                        descriptor_error!(InvalidLayoutDescriptor, pool_i, "field width", "1, 2, 4 or 8".to_string(), format!("{} (field {})", width, field_i));
+                    }
+
+                    if offset + width > obj_size {
+                        descriptor_error!(InvalidLayoutDescriptor, pool_i, "field offset", format!("{} or less", obj_size - width.min(obj_size)), format!("{} (field {})", offset, field_i));
+                    }
+
+                    (obj_size, offset, width)
+                }
+            };
+        }
+
+        // spec: オブジェクトは配列と同様に先頭にバイトサイズを持つ
+        macro_rules! obj_field_ptr {
+            ($obj_ptr:expr, $offset:expr, $width:expr) => {
+                {
+                    let obj_ptr = $obj_ptr;
+
+                    if $offset + $width > *(obj_ptr as *mut usize) {
+                        exit!(ArrayAccessViolation);
+                    }
+
+                    ((obj_ptr as *mut usize).add(1) as *mut u8).add($offset)
+                }
+            };
+        }
+
+        // note: 配列と要素インデックスをポップし、要素をアトミック型として参照
+        // spec: メモリオーダーは SeqCst
+        macro_rules! atomic_arr_elem {
+            ($ty:ty, $atomic_ty:ty) => {
+                {
+                    let arr_i = stack_pop!(usize);
+                    let arr_ptr = stack_pop!(*mut c_void);
+                    let arr_size = *(arr_ptr as *mut usize);
+
+                    if (arr_i + 1) * size_of::<$ty>() > arr_size {
+                        exit!(ArrayAccessViolation);
+                    }
+
+                    let arr_top_ptr = (arr_ptr as *mut usize).add(1);
+                    &*((arr_top_ptr as *mut $ty).add(arr_i) as *const $atomic_ty)
+                }
+            };
+        }
+
+        macro_rules! atomic_arr {
+            ($ty:ty, $atomic_ty:ty, load) => {
+                {
+                    let elem = atomic_arr_elem!($ty, $atomic_ty);
+                    stack_push!($ty, elem.load(Ordering::SeqCst));
+                }
+            };
+
+            ($ty:ty, $atomic_ty:ty, store) => {
+                {
+                    let value = stack_pop!($ty);
+                    let elem = atomic_arr_elem!($ty, $atomic_ty);
+                    elem.store(value, Ordering::SeqCst);
+                    escape_arr!(value);
+                }
+            };
+
+            // spec: 交換の成否に関わらず直前の値をプッシュ
+            ($ty:ty, $atomic_ty:ty, compare_exchange) => {
+                {
+                    let new_value = stack_pop!($ty);
+                    let expected_value = stack_pop!($ty);
+                    let elem = atomic_arr_elem!($ty, $atomic_ty);
+
+                    let prev_value = match elem.compare_exchange(expected_value, new_value, Ordering::SeqCst, Ordering::SeqCst) {
+                        Ok(v) => v,
+                        Err(v) => v,
+                    };
+
+                    if prev_value == expected_value {
+                        escape_arr!(new_value);
+                    }
+
+                    stack_push!($ty, prev_value);
+                }
+            };
+
+            // spec: ラップアラウンドで加算し、直前の値をプッシュ
+            ($ty:ty, $atomic_ty:ty, fetch_add) => {
+                {
+                    let value = stack_pop!($ty);
+                    let elem = atomic_arr_elem!($ty, $atomic_ty);
+                    let prev_value = elem.fetch_add(value, Ordering::SeqCst);
+                    escape_arr!(prev_value.wrapping_add(value));
+                    stack_push!($ty, prev_value);
+                }
+            };
+        }
+
+        // note: 配列が len 要素以上を持つかチェックし、先頭要素へのポインタを返す
+        macro_rules! vector_arr_ptr {
+            ($ty:ty, $arr_ptr:expr, $len:expr) => {
+                {
+                    let arr_ptr = $arr_ptr;
+
+                    match $len.checked_mul(size_of::<$ty>()) {
+                        Some(region_size) if region_size <= *(arr_ptr as *mut usize) => (),
+                        _ => exit!(ArrayAccessViolation),
+                    }
+
+                    (arr_ptr as *mut usize).add(1) as *mut $ty
+                }
+            };
+        }
+
+        // spec: (dst, left, right, len) をポップし、先頭から len 要素について dst[i] = left[i] op right[i]
+        // spec: 整数はラップアラウンド、浮動小数点数は NaN でもトラップしない; dst は left/right と同一の配列でも可
+        macro_rules! vector_calc {
+            ($ty:ty, |$left_term:ident, $right_term:ident| $calc:expr) => {
+                {
+                    let len = stack_pop!(usize);
+                    let right_ptr = vector_arr_ptr!($ty, stack_pop!(*mut c_void), len);
+                    let left_ptr = vector_arr_ptr!($ty, stack_pop!(*mut c_void), len);
+                    let dst_ptr = vector_arr_ptr!($ty, stack_pop!(*mut c_void), len);
+                    charge_fuel!(len);
+
+                    for i in 0..len {
+                        let $left_term = *left_ptr.add(i);
+                        let $right_term = *right_ptr.add(i);
+                        *dst_ptr.add(i) = $calc;
+                    }
+
+                    trace!("{}", format!("[{} elements]", len).bright_green().dimmed());
+                    trace!();
+                }
+            };
+        }
+
+        // spec: 配列の先頭から len 要素を value で埋める
+        macro_rules! fill_arr {
+            ($ty:ty, $pop_ty:ty) => {
+                {
+                    let len = stack_pop!(usize);
+                    let value = narrow!(stack_pop!($pop_ty), $ty);
+                    let arr_ptr = stack_pop!(*mut c_void);
+                    let arr_size = *(arr_ptr as *mut usize);
+
+                    match len.checked_mul(size_of::<$ty>()) {
+                        Some(fill_size) if fill_size <= arr_size => charge_fuel!(fill_size.div_ceil(8)),
+                        _ => exit!(ArrayAccessViolation),
+                    }
+
+                    let arr_top_ptr = (arr_ptr as *mut usize).add(1) as *mut $ty;
+
+                    std::slice::from_raw_parts_mut(arr_top_ptr, len).fill(value);
+
+                    if size_of::<$ty>() >= size_of::<u32>() && len != 0 {
+                        escape_arr!(value);
+                    }
+
+                    trace!("{}", format!("[fill {} elements / {} byte size / value 0x{:0x}]", len, arr_size, value).bright_green().dimmed());
+                    trace!();
                 }
             };
         }
@@ -470,14 +1916,17 @@ impl Interpreter {
                         exit!($err_status);
                     }
 
-                    *($ptr as *mut $ty).sub(1)
+                    std::ptr::read_unaligned(($ptr as *mut $ty).sub(1))
                 }
             };
         }
 
         macro_rules! unsafe_stack_top {
             ($ty:ty) => {
-                top!(stack_ptr, sp, $ty, StackOverflow)
+                {
+                    audit_alignment!(stack_ptr.sub(size_of::<$ty>()), $ty);
+                    top!(stack_ptr, sp, $ty, StackOverflow)
+                }
             };
         }
 
@@ -485,7 +1934,7 @@ impl Interpreter {
             ($ty:ty) => {
                 {
                     // note: リターンアドレス以前の値にアクセスしないようチェック
-                    if sp < bp + size_of::<usize>() * 2 + size_of::<$ty>() {
+                    if sp < bp + frame_header_size + size_of::<$ty>() {
                         exit!(StackAccessViolation);
                     }
 
@@ -504,7 +1953,7 @@ impl Interpreter {
                     }
 
                     let tmp_ptr = $ptr as *mut $ty;
-                    let value = *tmp_ptr;
+                    let value = std::ptr::read_unaligned(tmp_ptr);
                     $ptr = (tmp_ptr as *mut c_void).add(value_size);
                     $curr_pos += value_size;
 
@@ -525,6 +1974,7 @@ impl Interpreter {
             };
         }
 
+        #[cfg(feature = "trace")]
         macro_rules! raw_ptr_to_string {
             ($ptr:expr, $size:expr) => {
                 {
@@ -548,89 +1998,946 @@ impl Interpreter {
         }
 
         macro_rules! calc {
-            ($ty:ty, $f:ident$(, $check_divide_by_zero:expr)?) => {
+            // spec: シフト量はビット幅でマスクされ、トラップしない
+            ($ty:ty, $f:ident, masked_shift$(as $signed_ty:ty)?) => {
+                {
+                    let shift_amount = stack_pop!(u32);
+                    let left_term = stack_pop!($ty);
+                    let value = (left_term$(as $signed_ty)?).$f(shift_amount) as $ty;
+                    stack_push!($ty, value);
+                }
+            };
+
+            // spec: オーバーフロー時はトラップせず型の最大値・最小値に丸める
+            ($ty:ty, $f:ident, saturating) => {
                 {
                     let right_term = stack_pop!($ty);
                     let left_term = stack_pop!($ty);
+                    stack_push!($ty, left_term.$f(right_term));
+                }
+            };
+
+            // note: 2 項をポップした後の計算部分
+            (@apply $f:ident, $left_term:expr, $right_term:expr$(, $check_divide_by_zero:expr)?) => {
+                {
+                    let right_term = $right_term;
+                    let left_term = $left_term;
+
+                    $(
+                        if $check_divide_by_zero && right_term == 0 {
+                            exit!(DivideByZero);
+                        }
+                    )?
+
+                    let (value, overflowing) = left_term.$f(right_term);
+
+                    if overflowing && config.trap_on_overflow {
+                        exit!(ArithmeticOverflow);
+                    }
+
+                    value
+                }
+            };
+
+            ($ty:ty, $f:ident$(, $check_divide_by_zero:expr)?) => {
+                {
+                    // note: トレース系の設定が無効で 2 項が揃っていれば、チェックを 1 度で済ませスタック上で直接計算
+                    if !config.stack_delta_trace && !config.audit_alignment && sp >= bp + frame_header_size + size_of::<$ty>() * 2 {
+                        let right_ptr = stack_ptr.sub(size_of::<$ty>()) as *mut $ty;
+                        let left_ptr = stack_ptr.sub(size_of::<$ty>() * 2) as *mut $ty;
+
+                        // spec: トラップ時のスタックは通常のパスと同様に 2 項をポップした状態
+                        sp -= size_of::<$ty>() * 2;
+                        stack_ptr = left_ptr as *mut c_void;
+
+                        let value = calc!(@apply $f, std::ptr::read_unaligned(left_ptr), std::ptr::read_unaligned(right_ptr)$(, $check_divide_by_zero)?);
+
+                        std::ptr::write_unaligned(left_ptr, value);
+                        sp += size_of::<$ty>();
+                        stack_ptr = right_ptr as *mut c_void;
+                    } else {
+                        let right_term = stack_pop!($ty);
+                        let left_term = stack_pop!($ty);
+                        let value = calc!(@apply $f, left_term, right_term$(, $check_divide_by_zero)?);
+                        stack_push!($ty, value);
+                    }
+                }
+            };
+        }
+
+        macro_rules! bit_calc {
+            ($ty:ty, $op:tt) => {
+                {
+                    let right_term = stack_pop!($ty);
+                    let left_term = stack_pop!($ty);
+                    stack_push!($ty, left_term $op right_term);
+                }
+            };
+        }
+
+        // spec: $via_ty を経由してキャストする (符号拡張または切り捨て)
+        macro_rules! convert {
+            ($from_ty:ty, $to_ty:ty, $($via_ty:ty),+) => {
+                {
+                    let value = stack_pop!($from_ty);
+                    stack_push!($to_ty, value $(as $via_ty)+ as $to_ty);
+                }
+            };
+        }
+
+        // spec: 0 方向に丸めた値を符号付き整数に変換; 表現できない値は float_to_int_policy に従う
+        macro_rules! float_to_int {
+            ($from_ty:ty, $to_ty:ty, $signed_ty:ty) => {
+                {
+                    let value = stack_pop!($from_ty);
+
+                    if config.float_to_int_policy == FloatToIntPolicy::Saturate {
+                        // note: as による変換は飽和し、NaN は 0 になる
+                        stack_push!($to_ty, value as $signed_ty as $to_ty);
+                    } else {
+                        if value.is_nan() {
+                            exit!(InvalidFloatOperation);
+                        }
+
+                        let truncated_value = value.trunc();
+
+                        if truncated_value < <$signed_ty>::MIN as $from_ty || truncated_value >= -(<$signed_ty>::MIN as $from_ty) {
+                            exit!(ArithmeticOverflow);
+                        }
+
+                        stack_push!($to_ty, truncated_value as $signed_ty as $to_ty);
+                    }
+                }
+            };
+        }
+
+        // spec: 2 の補数表現の符号付き整数として扱い、最小値の符号反転はオーバーフロー
+        macro_rules! neg {
+            ($ty:ty, $signed_ty:ty) => {
+                neg!($ty, $signed_ty, overflowing_neg)
+            };
+
+            ($ty:ty, $signed_ty:ty, $f:ident) => {
+                {
+                    let value = stack_pop!($ty) as $signed_ty;
+                    let (neg_value, overflowing) = value.$f();
+
+                    if overflowing && config.trap_on_overflow {
+                        exit!(ArithmeticOverflow);
+                    }
+
+                    stack_push!($ty, neg_value as $ty);
+                }
+            };
+        }
+
+        // spec: プール要素の値をそのままプッシュ (浮動小数点数もビット列として扱う)
+        macro_rules! load_const {
+            ($ty:ty) => {
+                {
+                    let pool_i = next_prg!(usize);
+                    jump_pool_to!(pool_i);
+                    let value = next_pool!($ty);
+                    stack_push!($ty, value);
+
+                    trace!("{}", format!("[pool index 0x{:0x} / value 0x{:0x}]", pool_i, value).bright_green().dimmed());
+                    trace!();
+                }
+            };
+        }
+
+        // spec: バイト配列内の任意オフセットからリトルエンディアンで読み込み
+        macro_rules! load_arr_bytes {
+            ($ty:ty, $push_ty:ty) => {
+                {
+                    let offset = stack_pop!(usize);
+                    let arr_ptr = stack_pop!(*mut c_void);
+                    let arr_size = *(arr_ptr as *mut usize);
+
+                    match offset.checked_add(size_of::<$ty>()) {
+                        Some(end) if end <= arr_size => (),
+                        _ => exit!(ArrayAccessViolation),
+                    }
+
+                    let arr_top_ptr = (arr_ptr as *mut usize).add(1) as *mut u8;
+                    let value = <$ty>::from_le(std::ptr::read_unaligned(arr_top_ptr.add(offset) as *const $ty));
+                    stack_push!($push_ty, value as $push_ty);
+
+                    trace!("{}", format!("[offset {} / {} byte size / value 0x{:0x}]", offset, arr_size, value).bright_green().dimmed());
+                    trace!();
+                }
+            };
+        }
+
+        // spec: バイト配列内の任意オフセットへリトルエンディアンで書き込み
+        macro_rules! store_arr_bytes {
+            ($ty:ty, $pop_ty:ty) => {
+                {
+                    let value = narrow!(stack_pop!($pop_ty), $ty);
+                    let offset = stack_pop!(usize);
+                    let arr_ptr = stack_pop!(*mut c_void);
+                    let arr_size = *(arr_ptr as *mut usize);
+
+                    match offset.checked_add(size_of::<$ty>()) {
+                        Some(end) if end <= arr_size => (),
+                        _ => exit!(ArrayAccessViolation),
+                    }
+
+                    let arr_top_ptr = (arr_ptr as *mut usize).add(1) as *mut u8;
+                    std::ptr::write_unaligned(arr_top_ptr.add(offset) as *mut $ty, value.to_le());
+
+                    if size_of::<$ty>() >= size_of::<u32>() {
+                        escape_arr!(value);
+                    }
+
+                    trace!("{}", format!("[offset {} / {} byte size / change value to 0x{:0x}]", offset, arr_size, value).bright_green().dimmed());
+                    trace!();
+                }
+            };
+        }
+
+        // spec: 結果は 32 ビット値としてプッシュ
+        macro_rules! count_bits {
+            ($ty:ty, $f:ident) => {
+                {
+                    let value = stack_pop!($ty);
+                    stack_push!(u32, value.$f());
+                }
+            };
+        }
+
+        // spec: IOrd などと同様に符号なしで比較
+        macro_rules! select {
+            ($ty:ty, $f:ident) => {
+                {
+                    let value2 = stack_pop!($ty);
+                    let value1 = stack_pop!($ty);
+                    stack_push!($ty, value1.$f(value2));
+                }
+            };
+        }
+
+        // spec: trap_on_nan が有効なら NaN でない入力から NaN を生成する演算は InvalidFloatOperation で終了 (入力の NaN は伝播)
+        macro_rules! float_calc {
+            ($ty:ty, $op:tt) => {
+                {
+                    let right_term = stack_pop!($ty);
+                    let left_term = stack_pop!($ty);
+                    let value = left_term $op right_term;
+
+                    if config.trap_on_nan && value.is_nan() && !left_term.is_nan() && !right_term.is_nan() {
+                        exit!(InvalidFloatOperation);
+                    }
+
+                    stack_push!($ty, value);
+                }
+            };
+        }
+
+        // spec: float_calc! と同様に NaN の生成は trap_on_nan に従う
+        macro_rules! float_math {
+            ($ty:ty, $f:ident) => {
+                {
+                    let value = stack_pop!($ty);
+                    let result = value.$f();
+
+                    if config.trap_on_nan && result.is_nan() && !value.is_nan() {
+                        exit!(InvalidFloatOperation);
+                    }
+
+                    stack_push!($ty, result);
+                }
+            };
+
+            ($ty:ty, $f:ident, binary) => {
+                {
+                    let right_term = stack_pop!($ty);
+                    let left_term = stack_pop!($ty);
+                    let result = left_term.$f(right_term);
+
+                    if config.trap_on_nan && result.is_nan() && !left_term.is_nan() && !right_term.is_nan() {
+                        exit!(InvalidFloatOperation);
+                    }
+
+                    stack_push!($ty, result);
+                }
+            };
+        }
+
+        // spec: IEEE 754 の totalOrder に従い -1/0/1 をプッシュ (NaN も順序付けされトラップしない)
+        macro_rules! float_total_cmp {
+            ($ty:ty) => {
+                {
+                    let value2 = stack_pop!($ty);
+                    let value1 = stack_pop!($ty);
+                    stack_push!(u32, value1.total_cmp(&value2) as i32 as u32);
+                }
+            };
+        }
+
+        // spec: IOrd などと同様に符号なしで比較し、-1/0/1 をプッシュ
+        macro_rules! int_cmp {
+            ($ty:ty) => {
+                {
+                    let value2 = stack_pop!($ty);
+                    let value1 = stack_pop!($ty);
+                    stack_push!(u32, value1.cmp(&value2) as i32 as u32);
+                }
+            };
+        }
+
+        // spec: -1/0/1 をプッシュし、いずれかが NaN の場合は $nan_value をプッシュ (トラップしない)
+        macro_rules! float_cmp {
+            ($ty:ty, $nan_value:expr) => {
+                {
+                    let value2 = stack_pop!($ty);
+                    let value1 = stack_pop!($ty);
+
+                    let result = match value1.partial_cmp(&value2) {
+                        Some(ordering) => ordering as i32,
+                        None => $nan_value,
+                    };
+
+                    stack_push!(u32, result as u32);
+                }
+            };
+        }
+
+        macro_rules! goto {
+            () => {
+                goto!(i16)
+            };
+
+            ($offset_ty:ty) => {
+                {
+                    let offset = next_prg!($offset_ty);
+                    let inst_i = pc as isize + offset as isize;
+
+                    trace!("{}", format!("[goto 0x{:0x}]", inst_i).bright_green().dimmed());
+                    trace!();
+
+                    if 0 > inst_i {
+                        exit!(BytecodeAccessViolation);
+                    }
+
+                    jump_prg_to!(inst_i as usize);
+                }
+            };
+        }
+
+        macro_rules! goto_if {
+            ($cond:expr) => {
+                goto_if!($cond, i16)
+            };
+
+            ($cond:expr, $offset_ty:ty) => {
+                {
+                    trace!("{}", format!("[{}]", if $cond { format!("jump to 0x{:0x}", pc) } else { "no jump".to_string() }).bright_green().dimmed());
+                    trace!();
+
+                    if $cond {
+                        goto!($offset_ty);
+                    } else {
+                        next_prg!($offset_ty);
+                    }
+                }
+            };
+        }
+
+        // note: 2 値をポップして比較し、条件を満たせば分岐
+        macro_rules! goto_if_cmp {
+            ($ty:ty, $op:tt) => {
+                {
+                    let value2 = stack_pop!($ty);
+                    let value1 = stack_pop!($ty);
+                    let cond = value1 $op value2;
+                    goto_if!(cond);
+                }
+            };
+        }
+
+        // note: 分岐先がバイトコード範囲内かチェック
+        macro_rules! branch_target {
+            ($base:expr, $offset:expr) => {
+                {
+                    let inst_i = $base as isize + $offset as isize;
+
+                    if 0 > inst_i || inst_i as usize >= bytecode_len {
+                        exit!(BytecodeAccessViolation);
+                    }
+
+                    inst_i as usize
+                }
+            };
+        }
+
+        // spec: オフセットは命令末尾 (テーブル直後) からの相対位置
+        macro_rules! table_switch {
+            () => {
+                {
+                    let key = stack_pop!(u32);
+                    let default_offset = next_prg!(i16);
+                    let low = next_prg!(u32);
+                    let high = next_prg!(u32);
+
+                    if low > high {
+                        exit!(BytecodeAccessViolation);
+                    }
+
+                    let table_len = (high - low) as usize + 1;
+                    let table_end = pc + table_len * size_of::<i16>();
+
+                    if table_end > bytecode_len {
+                        exit!(BytecodeAccessViolation);
+                    }
+
+                    // spec: 分岐先の表全体は読み込み時に検査するため、実行時は選択された分岐先のみを検査
+                    let offset = if (low..=high).contains(&key) {
+                        std::ptr::read_unaligned(inst_ptr.add((key - low) as usize * size_of::<i16>()) as *mut i16)
+                    } else {
+                        default_offset
+                    };
+
+                    let target = branch_target!(table_end, offset);
+
+                    trace!("{}", format!("[key 0x{:0x} / jump to 0x{:0x}]", key, target).bright_green().dimmed());
+                    trace!();
+
+                    jump_prg_to!(target);
+                }
+            };
+        }
+
+        // spec: (キー, オフセット) の組はキーの昇順に並ぶ
+        // spec: オフセットは命令末尾 (組の直後) からの相対位置
+        macro_rules! lookup_switch {
+            () => {
+                {
+                    let key = stack_pop!(u32);
+                    let default_offset = next_prg!(i16);
+                    let pair_len = next_prg!(u32) as usize;
+                    let pair_size = size_of::<u32>() + size_of::<i16>();
+                    let pairs_ptr = inst_ptr;
+                    let pairs_end = pc + pair_len * pair_size;
+
+                    if pairs_end > bytecode_len {
+                        exit!(BytecodeAccessViolation);
+                    }
+
+                    let mut offset = default_offset;
+                    let mut low = 0usize;
+                    let mut high = pair_len;
+
+                    while low < high {
+                        let mid = (low + high) / 2;
+                        let pair_ptr = pairs_ptr.add(mid * pair_size);
+                        let pair_key = std::ptr::read_unaligned(pair_ptr as *mut u32);
+
+                        if pair_key == key {
+                            offset = std::ptr::read_unaligned(pair_ptr.add(size_of::<u32>()) as *mut i16);
+                            break;
+                        }
+
+                        if pair_key < key {
+                            low = mid + 1;
+                        } else {
+                            high = mid;
+                        }
+                    }
+
+                    let target = branch_target!(pairs_end, offset);
+
+                    trace!("{}", format!("[key 0x{:0x} / jump to 0x{:0x}]", key, target).bright_green().dimmed());
+                    trace!();
+
+                    jump_prg_to!(target);
+                }
+            };
+        }
+
+        // note: フレーム情報を破棄し、計測結果に反映
+        macro_rules! pop_frame_info {
+            () => {
+                if let Some(frame_info) = frame_infos.pop() {
+                    if config.record_stack_depth {
+                        let max_depth = report.max_operand_depths.entry(frame_info.pool_i).or_insert(0);
+                        *max_depth = (*max_depth).max(frame_info.max_operand_depth);
+                    }
+                }
+            };
+        }
+
+        // note: 保存済み bp とリターンアドレスをプッシュ
+        macro_rules! push_frame_link {
+            ($saved_bp:expr, $ret_addr:expr) => {
+                if config.side_return_stack {
+                    if return_stack.len() >= max_stack_size / (size_of::<usize>() * 2) {
+                        exit!(StackOverflow);
+                    }
+
+                    return_stack.push(($saved_bp, $ret_addr));
+                } else {
+                    stack_push!(usize, $saved_bp);
+                    stack_push!(usize, $ret_addr);
+                }
+            };
+        }
+
+        // note: 保存済み bp とリターンアドレスをポップ
+        macro_rules! pop_frame_link {
+            () => {
+                if config.side_return_stack {
+                    match return_stack.pop() {
+                        Some(v) => v,
+                        None => exit!(StackAccessViolation),
+                    }
+                } else {
+                    let ret_addr = unsafe_stack_pop!(usize);
+                    let saved_bp = unsafe_stack_pop!(usize);
+                    (saved_bp, ret_addr)
+                }
+            };
+        }
+
+        // note: 現フレームのリターンアドレス
+        macro_rules! frame_ret_addr {
+            () => {
+                if config.side_return_stack {
+                    match return_stack.last() {
+                        Some(v) => v.1,
+                        None => exit!(StackAccessViolation),
+                    }
+                } else {
+                    unsafe_stack_top!(usize)
+                }
+            };
+        }
+
+        // note: 関数プール要素に従ってフレームを作成し、開始アドレスにジャンプ
+        // spec: 末尾呼び出しでは現フレームを破棄し、bp とリターンアドレスを再利用
+        // spec: 引数の受け渡し
+        // spec: - 呼び出し元は引数を先頭から順にプッシュし、呼び出し先の変数 0 から順に同じ並びで格納される
+        // spec: - DESCRIPTOR_FLAG_SLOT_KINDS 未指定時は各引数・変数が 4 バイト (Long / Double や参照は 2 要素分として数える)
+        // spec: - DESCRIPTOR_FLAG_SLOT_KINDS 指定時は各変数が種別の幅 (Long / Double は 8 バイト, Ref は usize) を持ち、引数も同じ幅でプッシュする
+        // spec: - 変数テーブルはフレーム先頭 (保存済み bp とリターンアドレスの後ろ) に先頭から詰めて配置
+        // spec: - Load / Store などの変数インデックスは SLOT_KINDS 指定時は変数の番号 (変数の幅と異なる幅でのアクセスは StackAccessViolation)、未指定時は 4 バイト単位の位置
+        // spec: - 可変長引数と戻り値の受け渡しは下記 (可変長引数) および ret! を参照
+        macro_rules! invoke {
+            ($pool_i:expr) => {
+                invoke!($pool_i, false)
+            };
+
+            ($pool_i:expr, $is_tail_call:expr) => {
+                {
+                    let pool_i = $pool_i;
+                    jump_pool_to!(pool_i);
+                    let start_addr = next_pool!(usize);
+                    let var_len = next_pool!(u16) as usize;
+                    let raw_arg_len = next_pool!(u8) as usize;
+
+                    // spec: arg_len の最上位ビットが 1 の場合は可変長引数関数
+                    // spec: 呼び出し元は固定引数, 追加の引数 (各 u32), 追加の引数の個数 (u32) の順にプッシュ
+                    // spec: 追加の引数は配列にまとめられ、その参照が固定引数の後ろに 2 要素分 (DESCRIPTOR_FLAG_SLOT_KINDS 指定時は Ref 変数 1 つ分) の引数として渡される
+                    let is_variadic = raw_arg_len & 0x80 != 0;
+
+                    // spec: 呼び出し元で許可されていないコール番号は呼び出し先でも許可しない
+                    let call_allow_list = if has_call_allow_lists {
+                        let allow_len = next_pool!(u8) as usize;
+                        let mut allow_list = Vec::<u8>::new();
+
+                        for _ in 0..allow_len {
+                            allow_list.push(next_pool!(u8));
+                        }
+
+                        if let Some(caller_allow_list) = frame_infos.last().and_then(|each_info| each_info.call_allow_list.as_ref()) {
+                            allow_list.retain(|each_code| caller_allow_list.contains(each_code));
+                        }
+
+                        Some(allow_list)
+                    } else {
+                        frame_infos.last().and_then(|each_info| each_info.call_allow_list.clone())
+                    };
+
+                    // spec: 種別は 0 から順に Int, Long, Float, Double, Ref
+                    let slot_kinds = if has_slot_kinds {
+                        let mut kinds = Vec::<SlotKind>::new();
+
+                        for var_i in 0..var_len {
+                            let kind_value = next_pool!(u8);
+
+                            match FromPrimitive::from_u8(kind_value) {
+                                Some(v) => kinds.push(v),
+                                None => descriptor_error!(InvalidFunctionDescriptor, pool_i, "slot kind", "0x0 - 0x4".to_string(), format!("0x{:0x} (variable {})", kind_value, var_i)),
+                            }
+                        }
+
+                        Some(kinds)
+                    } else {
+                        None
+                    };
+
+                    // spec: 変数を先頭から順に種別の幅に応じて配置
+                    let slot_offsets = match &slot_kinds {
+                        Some(kinds) => {
+                            let mut offsets = Vec::<usize>::new();
+                            let mut table_size = 0usize;
+
+                            for each_kind in kinds {
+                                offsets.push(table_size);
+                                table_size += each_kind.width();
+                            }
+
+                            offsets.push(table_size);
+                            Some(offsets)
+                        },
+                        None => None,
+                    };
+
+                    let variadic_arg_len = if slot_offsets.is_some() { 1 } else { size_of::<usize>() / size_of::<u32>() };
+                    let arg_len = (raw_arg_len & 0x7f) + if is_variadic { variadic_arg_len } else { 0 };
+
+                    let ret_len = if has_ret_len { next_pool!(u8) as usize } else { 0 };
+
+                    if var_len < arg_len {
+                        descriptor_error!(InvalidFunctionDescriptor, pool_i, "arg_len", format!("{} (var_len) or less", var_len), arg_len.to_string());
+                    }
+
+                    // spec: 可変長引数の配列を受け取る変数は Ref とする
+                    if let (true, Some(kinds)) = (is_variadic, &slot_kinds) {
+                        if kinds[arg_len - 1] != SlotKind::Ref {
+                            descriptor_error!(InvalidFunctionDescriptor, pool_i, "slot kind", "Ref (variadic arguments)".to_string(), format!("{:?} (variable {})", kinds[arg_len - 1], arg_len - 1));
+                        }
+                    }
+
+                    if is_variadic {
+                        let extra_arg_len = stack_pop!(u32) as usize;
+
+                        if sp < bp + frame_header_size + extra_arg_len * size_of::<u32>() {
+                            exit!(StackAccessViolation);
+                        }
+
+                        let extra_args_ptr = alloc_arr!(extra_arg_len * size_of::<u32>());
+
+                        // spec: auto_drop_arrays 有効時は呼び出し先のフレームが所有 (戻り値として返すか保存しなければ呼び出し先から戻る時に解放)
+                        if config.auto_drop_arrays && !$is_tail_call {
+                            let callee_depth = frame_infos.len() + 1;
+
+                            for each_arr in owned_arrs.iter_mut().filter(|(_, each_ptr)| *each_ptr == extra_args_ptr) {
+                                each_arr.0 = callee_depth;
+                            }
+                        }
+
+                        copy_nonoverlapping(stack_ptr.sub(extra_arg_len * size_of::<u32>()) as *const u8, (extra_args_ptr as *mut usize).add(1) as *mut u8, extra_arg_len * size_of::<u32>());
+                        stack_pop!(u32, extra_arg_len);
+                        stack_push!(*mut c_void, extra_args_ptr);
+                    }
+
+                    // spec: DESCRIPTOR_FLAG_SLOT_KINDS 指定時は各引数を対応する変数の幅でプッシュ (64 ビット変数は 2 要素分)
+                    let arg_slot_len = match &slot_offsets {
+                        Some(offsets) => offsets[arg_len] / size_of::<u32>(),
+                        None => arg_len,
+                    };
+
+                    if sp < arg_slot_len * size_of::<u32>() {
+                        exit!(StackAccessViolation);
+                    }
+
+                    // note: 引数値を事前にポップ
+                    let mut args = Vec::<u32>::new();
+
+                    for i in 0..arg_slot_len {
+                        let new_arg = std::ptr::read_unaligned((stack_ptr as *mut u32).sub(arg_slot_len - i));
+                        args.push(new_arg);
+                    }
+
+                    stack_pop!(u32, arg_slot_len);
+
+                    if $is_tail_call {
+                        // note: オペランドスタックと変数テーブルをポップ
+                        let pop_size = sp - bp - frame_header_size;
+                        unsafe_stack_pop!(u8, pop_size);
+                        let frame_depth = frame_infos.len();
+                        exception_handlers.retain(|each_handler| each_handler.call_depth != frame_depth);
+                        pop_frame_info!();
+                    }
+
+                    let ret_addr = if $is_tail_call { frame_ret_addr!() } else { pc };
+
+                    // note: bp とリターンアドレスをプッシュ & bp 設定
+                    if !$is_tail_call {
+                        let new_bp = sp;
+                        push_frame_link!(bp, ret_addr);
+                        bp = new_bp;
+                    }
+
+                    match &slot_offsets {
+                        Some(offsets) => {
+                            // note: 変数テーブルを確保し、先頭から引数を書き込み (変数は先頭から詰めて配置されるため引数の並びと一致)
+                            let table_top = sp;
+                            jump_stack_to!(sp + offsets[var_len]);
+                            copy_nonoverlapping(args.as_ptr() as *const u8, stack_ptr.sub(sp - table_top) as *mut u8, arg_slot_len * size_of::<u32>());
+                        },
+                        None => {
+                            // note: 引数をプッシュ
+                            for each_arg in args {
+                                stack_push!(u32, each_arg);
+                            }
+
+                            // note: 引数の要素分 (self 参照含む) をスキップ
+                            jump_stack_to!(sp + (var_len - arg_len) * size_of::<u32>());
+                        },
+                    }
+
+                    // note: 開始アドレスにジャンプ
+                    jump_prg_to!(start_addr);
+                    let table_size = match &slot_offsets {
+                        Some(offsets) => offsets[var_len],
+                        None => var_len * size_of::<u32>(),
+                    };
+
+                    frame_infos.push(FrameInfo::new(pool_i, table_size, call_allow_list, slot_offsets, ret_len, slot_kinds));
+
+                    trace!("{}", format!("[pool index 0x{:0x} / start at 0x{:0x} / return to 0x{:0x} / {} arguments]", pool_i, start_addr, ret_addr, arg_len).bright_green().dimmed());
+                    trace!();
+                }
+            };
+        }
+
+        // note: 戻り値の 32 ビット語と参照の下位 32 ビットが一致する配列を取り出す (escape_word! と同じ基準)
+        // spec: 現フレームが所有する配列のみ対象 (呼び出し元以前のフレームが所有する配列はそのまま)
+        macro_rules! take_ret_arrs {
+            ($ret_words:expr) => {
+                {
+                    let ret_words: &[u32] = $ret_words;
+                    let frame_depth = frame_infos.len();
+                    let mut ret_arrs = Vec::<*mut c_void>::new();
+
+                    if config.auto_drop_arrays {
+                        owned_arrs.retain(|(owner_depth, each_ptr)| {
+                            if *owner_depth != frame_depth || !ret_words.contains(&(*each_ptr as usize as u32)) {
+                                return true;
+                            }
+
+                            ret_arrs.push(*each_ptr);
+                            return false;
+                        });
+                    }
+
+                    ret_arrs
+                }
+            };
+        }
+
+        // note: フレームを破棄してリターンアドレスに戻る
+        // spec: 型を指定した場合は戻り値をポップし、呼び出し元のオペランドスタックにプッシュ
+        // spec: 戻り値に含まれる配列の所有権は呼び出し元フレームへ移す
+        macro_rules! ret {
+            (@frame $ret_arrs:expr, $ret_txt:expr) => {
+                {
+                    if sp < bp || sp - bp < frame_header_size {
+                        exit!(StackAccessViolation);
+                    }
+
+                    // note: 現フレームが所有する配列を解放
+                    if config.auto_drop_arrays {
+                        let frame_depth = frame_infos.len();
+                        let mut drop_count = 0usize;
+
+                        owned_arrs.retain(|(owner_depth, arr_ptr)| {
+                            if *owner_depth != frame_depth {
+                                return true;
+                            }
+
+                            free_arr!(*arr_ptr);
+                            drop_count += 1;
+                            return false;
+                        });
+
+                        trace!("{}", format!("[auto drop {} arrays]", drop_count).bright_green().dimmed());
+                    }
 
-                    $(
-                        if $check_divide_by_zero && right_term == 0 {
-                            exit!(DivideByZero);
-                        }
-                    )?
+                    // note: オペランドスタックと変数テーブルをポップ
+                    let pop_size = sp - bp - frame_header_size;
+                    unsafe_stack_pop!(u8, pop_size);
 
-                    let (value, overflowing) = left_term.$f(right_term);
+                    // note: pc 設定
+                    let (saved_bp, ret_addr) = pop_frame_link!();
+                    jump_prg_to!(ret_addr);
 
-                    if overflowing {
-                        exit!(ArithmeticOverflow);
+                    // note: 現フレームで登録された例外ハンドラを破棄
+                    let frame_depth = frame_infos.len();
+                    exception_handlers.retain(|each_handler| each_handler.call_depth != frame_depth);
+
+                    // note: bp 設定
+                    bp = saved_bp;
+                    pop_frame_info!();
+
+                    for each_arr in $ret_arrs {
+                        owned_arrs.push((frame_infos.len(), each_arr));
                     }
 
-                    stack_push!($ty, value);
+                    trace!("{}", format!("[return to 0x{:0x} / pop {} bytes / return {}]", ret_addr, pop_size, $ret_txt).bright_green().dimmed());
+                    trace!();
                 }
             };
-        }
 
-        macro_rules! goto {
+            // spec: DESCRIPTOR_FLAG_RET_LEN 指定時は関数プール要素で宣言された数の値を呼び出し元に残す (IRet などの型付きの Ret は従来どおり)
             () => {
                 {
-                    let offset = next_prg!(i16);
-                    let inst_i = pc as isize + offset as isize;
+                    let ret_len = frame_infos.last().map_or(0, |frame_info| frame_info.ret_len);
+
+                    if ret_len == 0 {
+                        ret!(@frame Vec::<*mut c_void>::new(), "void");
+                    } else {
+                        if sp < bp + frame_header_size + ret_len * size_of::<u32>() {
+                            exit!(StackAccessViolation);
+                        }
 
-                    println!("{}", format!("[goto 0x{:0x}]", inst_i).bright_green().dimmed());
-                    println!();
+                        let mut ret_values = Vec::<u32>::new();
 
-                    if 0 > inst_i {
-                        exit!(BytecodeAccessViolation);
+                        for i in 0..ret_len {
+                            ret_values.push(std::ptr::read_unaligned((stack_ptr as *mut u32).sub(ret_len - i)));
+                        }
+
+                        stack_pop!(u32, ret_len);
+
+                        let ret_arrs = take_ret_arrs!(&ret_values);
+                        ret!(@frame ret_arrs, format!("{} values", ret_len));
+
+                        for each_value in ret_values {
+                            stack_push!(u32, each_value);
+                        }
                     }
+                }
+            };
 
-                    jump_prg_to!(inst_i as usize);
+            ($ty:ty) => {
+                {
+                    let ret_value = stack_pop!($ty);
+                    let ret_bits = ret_value as u64;
+                    let ret_arrs = take_ret_arrs!(&[ret_bits as u32, (ret_bits >> 32) as u32]);
+                    ret!(@frame ret_arrs, format!("0x{:0x}", ret_value));
+                    stack_push!($ty, ret_value);
                 }
             };
         }
 
-        macro_rules! goto_if {
-            ($cond:expr) => {
+        // note: 例外ハンドラ登録時の状態に巻き戻し、例外値をプッシュしてハンドラにジャンプ
+        // spec: 範囲チェックは TryEnter 時に済んでいるため exit! を使わない
+        macro_rules! catch {
+            ($handler:expr, $value:expr) => {
                 {
-                    let jump_txt = if $cond { format!("jump to 0x{:0x}", pc) } else { "no jump".to_string() };
-                    println!("{}", format!("[{}]", jump_txt).bright_green().dimmed());
-                    println!();
+                    let handler = $handler;
+                    let value: u32 = $value;
+
+                    if config.auto_drop_arrays {
+                        owned_arrs.retain(|(owner_depth, arr_ptr)| {
+                            if *owner_depth <= handler.call_depth {
+                                return true;
+                            }
+
+                            free_arr!(*arr_ptr);
+                            return false;
+                        });
+                    }
 
-                    if $cond {
-                        goto!();
-                    } else {
-                        next_prg!(i16);
+                    stack_ptr = stack_ptr.sub(sp).add(handler.sp);
+                    sp = handler.sp;
+                    bp = handler.bp;
+                    while frame_infos.len() > handler.call_depth {
+                        pop_frame_info!();
+                    }
+                    return_stack.truncate(handler.call_depth);
+
+                    // spec: ハンドラ以降の命令数は新たに計測 (InstructionLimitExceeded の捕捉直後に再び上限を超えないように; 実行全体は max_instructions で制限)
+                    if let Some(frame_info) = frame_infos.last_mut() {
+                        frame_info.inst_count = 0;
                     }
+
+                    std::ptr::write_unaligned(stack_ptr as *mut u32, value);
+                    stack_ptr = stack_ptr.add(size_of::<u32>());
+                    sp += size_of::<u32>();
+
+                    inst_ptr = bytecode_ptr.add(handler.addr);
+                    pc = handler.addr;
+
+                    trace!("{}", format!("[catch 0x{:0x} / jump to 0x{:0x}]", value, handler.addr).bright_green().dimmed());
+                    trace!();
                 }
             };
         }
 
         if is_init_succeeded {
             // note: エントリポイント用のコールスタック要素をプッシュ
-            println!("{}", "<INVOKE ENTRY POINT>".blue());
-            println!();
-            // * ベースポインタ
-            stack_push!(usize, 0);
-            // * リターンアドレス
-            stack_push!(usize, bytecode_len - 1);
+            trace!("{}", "<INVOKE ENTRY POINT>".blue());
+            trace!();
+            // * ベースポインタ, リターンアドレス
+            push_frame_link!(0, bytecode_len - 1);
+        }
 
+        // note: エントリポイントのフレームをプッシュできなかった場合も実行しない
+        if is_init_succeeded {
             'operator: loop {
                 // note: 'operator ブロック内での終了処理
+                // spec: 例外ハンドラが登録されていれば捕捉可能な終了 (ExitStatus::is_catchable) は次のループ先頭で例外として捕捉
                 macro_rules! exit {
                     ($status_kind:ident) => {
                         {
                             es = ExitStatus::$status_kind as u32;
+
+                            if ExitStatus::$status_kind.is_catchable() && !exception_handlers.is_empty() {
+                                continue 'operator;
+                            }
+
                             break 'operator;
                         }
                     };
                 }
 
-                let tmp_pc = pc;
+                if es != ExitStatus::Success as u32 {
+                    if let Some(handler) = exception_handlers.pop() {
+                        catch!(handler, es);
+                        es = ExitStatus::Success as u32;
+                    }
+                }
+
+                #[cfg(feature = "trace")]
+                {
+                    opcode_pc = pc;
+                }
                 let opcode = next_prg!(u8);
                 let opcode_kind = Opcode::from(opcode);
 
-                println!("{}", format!("{} (0x{:0x} at 0x{:0x})", opcode_kind.to_string().to_uppercase(), opcode, tmp_pc).blue());
-                println!("{}", raw_ptr_to_string!(stack_ptr.sub(sp), sp).bright_black());
-                println!();
+                if config.record_opcode_histogram {
+                    opcode_counts[opcode as usize] += 1;
+                }
+
+                // spec: 命令の境界ごとに計測 (命令内で一時的に積まれる値は含まない)
+                if config.record_stack_depth {
+                    if let Some(frame_info) = frame_infos.last_mut() {
+                        let operand_depth = (sp - bp).saturating_sub(frame_header_size + frame_info.table_size);
+                        frame_info.max_operand_depth = frame_info.max_operand_depth.max(operand_depth);
+                    }
+                }
+
+                charge_fuel!(1);
+
+                if let Some(max_call_insts) = config.max_call_instructions {
+                    if let Some(frame_info) = frame_infos.last_mut() {
+                        frame_info.inst_count += 1;
+
+                        if frame_info.inst_count > max_call_insts {
+                            exit!(InstructionLimitExceeded);
+                        }
+                    }
+                }
+
+                trace!("{}", format!("{} (0x{:0x} at 0x{:0x})", opcode_kind.to_string().to_uppercase(), opcode, opcode_pc).blue());
+
+                if !config.stack_delta_trace {
+                    trace!("{}", raw_ptr_to_string!(stack_ptr.sub(sp), sp).bright_black());
+                    trace!();
+                }
 
                 match opcode_kind {
                     Opcode::Nop => (),
@@ -639,88 +2946,202 @@ impl Interpreter {
                         // todo: コード追加
                         let code = next_prg!(u8);
 
+                        if let Some(allow_list) = frame_infos.last().and_then(|each_info| each_info.call_allow_list.as_ref()) {
+                            if !allow_list.contains(&code) {
+                                trace!("{}", format!("[call number 0x{:0x} is not permitted in this function]", code).red());
+                                trace!();
+                                exit!(CallNotPermitted);
+                            }
+                        }
+
+                        // spec: ホストのファイルシステムへのアクセスは埋め込み側が file_calls で明示的に許可した場合のみ
+                        if (0x20..=0x24).contains(&code) && !config.file_calls {
+                            trace!("{}", format!("[call number 0x{:0x} requires file_calls]", code).red());
+                            trace!();
+                            exit!(CallNotPermitted);
+                        }
+
                         match code {
+                            // spec: 配列と最大バイト数 (usize) をポップし、配列の先頭から読み込んだバイト数 (u32) をプッシュ
+                            // spec: 最大バイト数は配列サイズで制限; 読み込みが要求より短い場合 (EOF など) はエラーとしない
                             0x00 => {
-                                let a = [0u8; 4].as_mut_ptr() as *mut c_void;
-                                let size = read(0, a, 4);
+                                let max_len = stack_pop!(usize);
+                                let arr_ptr = stack_pop!(*mut usize);
+                                let read_len = max_len.min(*arr_ptr);
+                                let (count, is_failed) = io_call!(read, 0, arr_ptr.add(1), read_len, false);
 
-                                println!("{} {}", size, raw_ptr_to_string!(a, 4));
+                                trace!("{}", "[console input]".bright_black());
+                                trace!("{}", raw_ptr_to_string!(arr_ptr.add(1), count).bright_black());
+                                trace!();
+                                io_value!(u32, count, is_failed);
                             },
+                            // spec: 配列をポップし、書き込んだバイト数 (u32) をプッシュ
                             0x01 => {
                                 let arr_ptr = stack_pop!(*mut usize);
                                 let arr_len = *arr_ptr;
 
-                                println!("{}", "[console output]".bright_black());
-                                println!("{}", raw_ptr_to_string!(arr_ptr.add(1), arr_len).bright_black());
-                                write(1, arr_ptr.add(1) as *mut c_void, arr_len as u32);
-                                println!();
+                                trace!("{}", "[console output]".bright_black());
+                                trace!("{}", raw_ptr_to_string!(arr_ptr.add(1), arr_len).bright_black());
+                                let (count, is_failed) = io_call!(write, 1, arr_ptr.add(1), arr_len, true);
+                                trace!();
+                                io_result!(count, is_failed, count < arr_len);
+                            },
+                            0x02 => load_arr_bytes!(u16, u32),
+                            0x03 => load_arr_bytes!(u32, u32),
+                            0x04 => load_arr_bytes!(u64, u64),
+                            0x05 => store_arr_bytes!(u16, u32),
+                            0x06 => store_arr_bytes!(u32, u32),
+                            0x07 => store_arr_bytes!(u64, u64),
+                            // spec: 多倍長整数の演算; 除算は 0 方向に丸める
+                            0x08 => big_int_calc!(+, false),
+                            0x09 => big_int_calc!(-, false),
+                            0x0a => big_int_calc!(*, true),
+                            0x0b => big_int_calc!(/, true, true),
+                            0x0c => {
+                                let right_term = pop_big_int!();
+                                let left_term = pop_big_int!();
+                                stack_push!(u32, left_term.cmp(&right_term) as i32 as u32);
+                            },
+                            // spec: 64 ビット値を符号付き整数として多倍長整数に変換
+                            0x0d => {
+                                let value = stack_pop!(u64) as i64;
+                                push_big_int!(BigInt::from(value));
+                            },
+                            // spec: 符号付き 64 ビット整数に収まらない場合は ArithmeticOverflow
+                            0x0e => {
+                                let value = match pop_big_int!().to_i64() {
+                                    Some(v) => v,
+                                    None => exit!(ArithmeticOverflow),
+                                };
+
+                                stack_push!(u64, value as u64);
+                            },
+                            // spec: 数学関数 (0x10 - 0x14 は f64, 0x18 - 0x1c は f32); log は自然対数
+                            0x10 => float_math!(f64, sqrt),
+                            0x11 => float_math!(f64, sin),
+                            0x12 => float_math!(f64, cos),
+                            0x13 => float_math!(f64, ln),
+                            0x14 => float_math!(f64, powf, binary),
+                            0x18 => float_math!(f32, sqrt),
+                            0x19 => float_math!(f32, sin),
+                            0x1a => float_math!(f32, cos),
+                            0x1b => float_math!(f32, ln),
+                            0x1c => float_math!(f32, powf, binary),
+                            // spec: ファイル (0x20 - 0x24); ファイルはハンドル (u32) で指定し、ホストのディスクリプタは公開しない
+                            // spec: open はパスのバイト配列とモード (u32; 0 は読み込み, 1 は書き込み (作成/切り詰め), 2 は追記, 3 は読み書き) をポップし、ハンドルをプッシュ
+                            0x20 => {
+                                let mode = stack_pop!(u32);
+                                let path_ptr = stack_pop!(*mut usize);
+                                let path = std::slice::from_raw_parts(path_ptr.add(1) as *const u8, *path_ptr);
+
+                                let flags = match mode {
+                                    0 => libc::O_RDONLY,
+                                    1 => libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC,
+                                    2 => libc::O_WRONLY | libc::O_CREAT | libc::O_APPEND,
+                                    3 => libc::O_RDWR | libc::O_CREAT,
+                                    _ => exit!(IoFailed),
+                                };
+
+                                // note: パスに NUL を含む場合は失敗扱い
+                                let fd = if path.contains(&0) {
+                                    -1
+                                } else {
+                                    let mut c_path = path.to_vec();
+                                    c_path.push(0);
+                                    open(c_path.as_ptr() as *const libc::c_char, flags, 0o644 as c_uint)
+                                };
+
+                                let handle = file_handles.len();
+
+                                if fd >= 0 {
+                                    file_handles.push(Some(fd));
+                                }
+
+                                trace!("{}", format!("[open {} / mode {} / handle {}]", String::from_utf8_lossy(path), mode, handle).bright_green().dimmed());
+                                trace!();
+                                io_value!(u32, handle, fd < 0);
+                            },
+                            // spec: read はハンドルと配列をポップし、配列サイズを上限に読み込んだバイト数をプッシュ
+                            0x21 => {
+                                let arr_ptr = stack_pop!(*mut usize);
+                                let fd = file_fd!(stack_pop!(u32));
+                                let (count, is_failed) = io_call!(read, fd, arr_ptr.add(1), *arr_ptr, false);
+                                io_value!(u32, count, is_failed);
+                            },
+                            // spec: write はハンドルと配列をポップし、標準出力への書き込みと同様に書き込んだバイト数をプッシュ
+                            0x22 => {
+                                let arr_ptr = stack_pop!(*mut usize);
+                                let arr_len = *arr_ptr;
+                                let fd = file_fd!(stack_pop!(u32));
+                                let (count, is_failed) = io_call!(write, fd, arr_ptr.add(1), arr_len, true);
+                                io_result!(count, is_failed, count < arr_len);
+                            },
+                            // spec: close はハンドルをポップ (閉じたハンドルは再利用しない)
+                            0x23 => {
+                                let handle = stack_pop!(u32);
+                                let fd = file_fd!(handle);
+                                file_handles[handle as usize] = None;
+
+                                if close(fd) < 0 && config.io_error_policy != IoErrorPolicy::ReturnCount {
+                                    exit!(IoFailed);
+                                }
+                            },
+                            // spec: seek はハンドル, オフセット (符号付き u64), 基準 (u32; 0 は先頭, 1 は現在位置, 2 は末尾) をポップし、新しい位置 (u64) をプッシュ
+                            0x24 => {
+                                let whence = match stack_pop!(u32) {
+                                    0 => libc::SEEK_SET,
+                                    1 => libc::SEEK_CUR,
+                                    2 => libc::SEEK_END,
+                                    _ => exit!(IoFailed),
+                                };
+
+                                let offset = stack_pop!(u64) as i64;
+                                let fd = file_fd!(stack_pop!(u32));
+                                let pos = lseek(fd, offset as libc::off_t, whence);
+                                io_value!(u64, pos, pos < 0);
+                            },
+                            // spec: インポート表を持つモジュールでは 0x80 + i はインポート表の i 番目の要素で名前解決したホストコールを指す
+                            _ => match match &import_calls {
+                                Some(calls) if code >= HOST_CALL_NUMBER_START => calls.get((code - HOST_CALL_NUMBER_START) as usize).copied().flatten().map(|call_i| &config.host_calls[call_i]),
+                                Some(_) => None,
+                                None => config.host_calls.iter().find(|each_call| each_call.number == code),
+                            } {
+                                Some(host_call) => {
+                                    if sp < bp + frame_header_size + host_call.arg_size {
+                                        exit!(StackAccessViolation);
+                                    }
+
+                                    let args = std::slice::from_raw_parts(stack_ptr.sub(host_call.arg_size) as *const u8, host_call.arg_size).to_vec();
+                                    unsafe_stack_pop!(u8, host_call.arg_size);
+
+                                    trace!("{}", format!("[host call '{}' / {} byte args]", host_call.name, host_call.arg_size).bright_green().dimmed());
+
+                                    let mut context = HostContext {
+                                        constants: &mut constants,
+                                        heap_size: &mut heap_size,
+                                        user_data: user_data.as_deref_mut(),
+                                    };
+
+                                    match (host_call.handler)(&mut context, &args) {
+                                        Some(rets) if rets.len() == host_call.ret_size => {
+                                            for each_byte in rets {
+                                                stack_push!(u8, each_byte);
+                                            }
+                                        },
+                                        _ => exit!(HostCallFailed),
+                                    }
+
+                                    trace!();
+                                },
+                                None => exit!(UnknownCallNumber),
                             },
-                            _ => exit!(UnknownCallNumber),
                         }
                     },
                     Opcode::Invoke => {
                         let pool_i = next_prg!(usize);
-                        jump_pool_to!(pool_i);
-                        let start_addr = next_pool!(usize);
-                        let var_len = next_pool!(u16) as usize;
-                        let arg_len = next_pool!(u8) as usize;
-
-                        if var_len < arg_len || sp < arg_len * size_of::<u32>() {
-                            exit!(StackAccessViolation);
-                        }
-
-                        // note: 引数値を事前にポップ
-                        let mut args = Vec::<u32>::new();
-
-                        for i in 0..arg_len {
-                            let new_arg = *((stack_ptr as *mut u32).sub(arg_len - i));
-                            args.push(new_arg);
-                        }
-
-                        stack_pop!(u32, arg_len);
-
-                        // note: bp をプッシュ & 設定
-                        let new_bp = sp;
-                        stack_push!(usize, bp);
-                        bp = new_bp;
-
-                        // note: リターンアドレスをプッシュ
-                        let ret_addr = pc;
-                        stack_push!(usize, ret_addr);
-
-                        // note: 引数をプッシュ
-                        for each_arg in args {
-                            stack_push!(u32, each_arg);
-                        }
-
-                        // note: 引数の要素分 (self 参照含む) をスキップ
-                        jump_stack_to!(sp + (var_len - arg_len) * size_of::<u32>());
-
-                        // note: 開始アドレスにジャンプ
-                        jump_prg_to!(start_addr);
-
-                        println!("{}", format!("[pool index 0x{:0x} / start at 0x{:0x} / return to 0x{:0x} / {} arguments]", pool_i, start_addr, ret_addr, arg_len).bright_green().dimmed());
-                        println!();
-                    },
-                    Opcode::Ret => {
-                        if sp < bp || sp - bp < size_of::<usize>() * 2 {
-                            exit!(StackAccessViolation);
-                        }
-
-                        // note: オペランドスタックと変数テーブルをポップ
-                        let pop_size = sp - bp - size_of::<usize>() * 2;
-                        unsafe_stack_pop!(u8, pop_size);
-
-                        // note: pc 設定
-                        let ret_addr = unsafe_stack_pop!(usize);
-                        jump_prg_to!(ret_addr);
-
-                        // note: bp 設定
-                        bp = unsafe_stack_pop!(usize);
-
-                        println!("{}", format!("[return to 0x{:0x} / pop {} bytes / return void]", ret_addr, pop_size).bright_green().dimmed());
-                        println!();
+                        invoke!(pool_i);
                     },
+                    Opcode::Ret => ret!(),
                     Opcode::BAPush => stack_push_arr!(u8),
                     Opcode::SAPush => stack_push_arr!(u16),
                     Opcode::IAPush => stack_push_arr!(u32),
@@ -769,9 +3190,14 @@ impl Interpreter {
                     Opcode::SAStore => store_arr!(u16, u32),
                     Opcode::IAStore => store_arr!(u32, u32),
                     Opcode::LAStore => store_arr!(u64, u64),
+                    // spec: 定数領域の配列は解放しない
                     Opcode::Drop => {
                         let ptr = stack_pop!(*mut c_void);
-                        free(ptr);
+                        owned_arrs.retain(|(_, each_ptr)| *each_ptr != ptr);
+
+                        if !constants.contains(ptr) {
+                            free_arr!(ptr);
+                        }
                     },
                     Opcode::IAdd => calc!(u32, overflowing_add),
                     Opcode::LAdd => calc!(u64, overflowing_add),
@@ -830,21 +3256,573 @@ impl Interpreter {
                         let cond = stack_pop!(u32) == 0;
                         goto_if!(cond);
                     },
+                    Opcode::FPush => stack_push_next_prg!(f32, f32),
+                    Opcode::DPush => stack_push_next_prg!(f64, f64),
+                    Opcode::FAdd => float_calc!(f32, +),
+                    Opcode::DAdd => float_calc!(f64, +),
+                    Opcode::FSub => float_calc!(f32, -),
+                    Opcode::DSub => float_calc!(f64, -),
+                    Opcode::FMul => float_calc!(f32, *),
+                    Opcode::DMul => float_calc!(f64, *),
+                    Opcode::FDiv => float_calc!(f32, /),
+                    Opcode::DDiv => float_calc!(f64, /),
+                    Opcode::IAnd => bit_calc!(u32, &),
+                    Opcode::LAnd => bit_calc!(u64, &),
+                    Opcode::IOr => bit_calc!(u32, |),
+                    Opcode::LOr => bit_calc!(u64, |),
+                    Opcode::IXor => bit_calc!(u32, ^),
+                    Opcode::LXor => bit_calc!(u64, ^),
+                    Opcode::INot => {
+                        let value = stack_pop!(u32);
+                        stack_push!(u32, !value);
+                    },
+                    Opcode::LNot => {
+                        let value = stack_pop!(u64);
+                        stack_push!(u64, !value);
+                    },
+                    Opcode::IShl => calc!(u32, wrapping_shl, masked_shift),
+                    Opcode::LShl => calc!(u64, wrapping_shl, masked_shift),
+                    Opcode::IShr => calc!(u32, wrapping_shr, masked_shift),
+                    Opcode::LShr => calc!(u64, wrapping_shr, masked_shift),
+                    Opcode::IAShr => calc!(u32, wrapping_shr, masked_shift as i32),
+                    Opcode::LAShr => calc!(u64, wrapping_shr, masked_shift as i64),
+                    Opcode::IRem => calc!(u32, overflowing_rem, true),
+                    Opcode::LRem => calc!(u64, overflowing_rem, true),
+                    Opcode::INeg => neg!(u32, i32),
+                    Opcode::LNeg => neg!(u64, i64),
+                    // spec: 符号拡張
+                    Opcode::I2L => convert!(u32, u64, i32, i64),
+                    // spec: 下位 32 ビットへ切り捨て
+                    Opcode::L2I => convert!(u64, u32, u32),
+                    // spec: 下位 8 ビットを符号拡張
+                    Opcode::B2I => convert!(u32, u32, u8, i8, i32),
+                    // spec: 下位 16 ビットへ切り捨てて符号拡張
+                    Opcode::I2S => convert!(u32, u32, u16, i16, i32),
+                    Opcode::Inc => {
+                        let var_i = next_prg!(u16);
+                        let inc_value = next_prg!(i8);
+
+                        // spec: DESCRIPTOR_FLAG_SLOT_KINDS 指定時は Int 変数のみ対象とする
+                        if let Some(kinds) = frame_infos.last().and_then(|each_info| each_info.slot_kinds.as_ref()) {
+                            if kinds.get(var_i as usize) != Some(&SlotKind::Int) {
+                                exit!(StackAccessViolation);
+                            }
+                        }
+
+                        let diff = var_table_diff!(u32, var_i);
+                        let ptr = stack_ptr.sub(diff) as *mut u32;
+                        audit_alignment!(ptr, u32);
+                        let (value, overflowing) = std::ptr::read_unaligned(ptr).overflowing_add_signed(inc_value as i32);
+
+                        if overflowing && config.trap_on_overflow {
+                            exit!(ArithmeticOverflow);
+                        }
+
+                        std::ptr::write_unaligned(ptr, value);
+                    },
+                    Opcode::TableSwitch => table_switch!(),
+                    Opcode::LookupSwitch => lookup_switch!(),
+                    Opcode::Swap => {
+                        let value2 = stack_pop!(u32);
+                        let value1 = stack_pop!(u32);
+                        stack_push!(u32, value2);
+                        stack_push!(u32, value1);
+                    },
+                    Opcode::Swap2 => {
+                        let value2 = stack_pop!(u64);
+                        let value1 = stack_pop!(u64);
+                        stack_push!(u64, value2);
+                        stack_push!(u64, value1);
+                    },
+                    // spec: v2, v1 → v1, v2, v1
+                    Opcode::DupX1 => {
+                        let value1 = stack_pop!(u32);
+                        let value2 = stack_pop!(u32);
+                        stack_push!(u32, value1);
+                        stack_push!(u32, value2);
+                        stack_push!(u32, value1);
+                    },
+                    // spec: v3, v2, v1 → v1, v3, v2, v1
+                    Opcode::DupX2 => {
+                        let value1 = stack_pop!(u32);
+                        let value2 = stack_pop!(u32);
+                        let value3 = stack_pop!(u32);
+                        stack_push!(u32, value1);
+                        stack_push!(u32, value3);
+                        stack_push!(u32, value2);
+                        stack_push!(u32, value1);
+                    },
+                    Opcode::FCmpT => float_total_cmp!(f32),
+                    Opcode::DCmpT => float_total_cmp!(f64),
+                    // spec: 関数参照は関数のプールインデックス
+                    Opcode::FuncRef => stack_push_next_prg!(usize, usize),
+                    // spec: 呼び出し先は FuncRef か MakeClosure で参照される関数に限る (読み込み時に検査されていない関数を呼び出さないように)
+                    Opcode::InvokeDynamic => {
+                        let pool_i = stack_pop!(usize);
+
+                        if !indirect_call_targets.contains(&pool_i) {
+                            descriptor_error!(InvalidFunctionDescriptor, pool_i, "indirect call target", "function referenced by FuncRef or MakeClosure".to_string(), format!("0x{:0x}", pool_i));
+                        }
+
+                        invoke!(pool_i);
+                    },
+                    Opcode::TailInvoke => {
+                        let pool_i = next_prg!(usize);
+                        invoke!(pool_i, true);
+                    },
+                    Opcode::Throw => {
+                        let value = stack_pop!(u32);
+
+                        match exception_handlers.pop() {
+                            Some(handler) => catch!(handler, value),
+                            None => {
+                                trace!("{}", format!("[uncaught exception 0x{:0x}]", value).red());
+                                trace!();
+                                exit!(UncaughtException);
+                            },
+                        }
+                    },
+                    // spec: プール要素はハンドラの開始アドレス
+                    Opcode::TryEnter => {
+                        let pool_i = next_prg!(usize);
+                        jump_pool_to!(pool_i);
+                        let handler_addr = next_pool!(usize);
+
+                        if handler_addr >= bytecode_len {
+                            exit!(BytecodeAccessViolation);
+                        }
+
+                        // note: 捕捉時に例外値をプッシュする領域を確保できるかチェック
+                        if sp + size_of::<u32>() > max_stack_size {
+                            exit!(StackOverflow);
+                        }
+
+                        exception_handlers.push(ExceptionHandler {
+                            addr: handler_addr,
+                            sp: sp,
+                            bp: bp,
+                            call_depth: frame_infos.len(),
+                        });
+                    },
+                    Opcode::TryLeave => {
+                        match exception_handlers.last() {
+                            Some(handler) if handler.call_depth == frame_infos.len() => {
+                                exception_handlers.pop();
+                            },
+                            _ => exit!(StackAccessViolation),
+                        }
+                    },
+                    Opcode::IRet => ret!(u32),
+                    Opcode::LRet => ret!(u64),
+                    Opcode::GotoW => goto!(i32),
+                    Opcode::IfW => {
+                        let cond = stack_pop!(u32) != 0;
+                        goto_if!(cond, i32);
+                    },
+                    Opcode::IfNotW => {
+                        let cond = stack_pop!(u32) == 0;
+                        goto_if!(cond, i32);
+                    },
+                    Opcode::IfICmpEq => goto_if_cmp!(u32, ==),
+                    Opcode::IfICmpNe => goto_if_cmp!(u32, !=),
+                    Opcode::IfICmpLt => goto_if_cmp!(u32, <),
+                    Opcode::IfICmpGe => goto_if_cmp!(u32, >=),
+                    Opcode::IfICmpGt => goto_if_cmp!(u32, >),
+                    Opcode::IfICmpLe => goto_if_cmp!(u32, <=),
+                    Opcode::IfLCmpEq => goto_if_cmp!(u64, ==),
+                    Opcode::IfLCmpNe => goto_if_cmp!(u64, !=),
+                    Opcode::IfLCmpLt => goto_if_cmp!(u64, <),
+                    Opcode::IfLCmpGe => goto_if_cmp!(u64, >=),
+                    Opcode::IfLCmpGt => goto_if_cmp!(u64, >),
+                    Opcode::IfLCmpLe => goto_if_cmp!(u64, <=),
+                    Opcode::Ldc => load_const!(u32),
+                    Opcode::Ldc2 => load_const!(u64),
+                    Opcode::LdStr => stack_push_str!(),
+                    Opcode::IMin => select!(u32, min),
+                    Opcode::IMax => select!(u32, max),
+                    Opcode::LMin => select!(u64, min),
+                    Opcode::LMax => select!(u64, max),
+                    Opcode::IAbs => neg!(u32, i32, overflowing_abs),
+                    Opcode::LAbs => neg!(u64, i64, overflowing_abs),
+                    Opcode::IRotl => calc!(u32, rotate_left, masked_shift),
+                    Opcode::IRotr => calc!(u32, rotate_right, masked_shift),
+                    Opcode::LRotl => calc!(u64, rotate_left, masked_shift),
+                    Opcode::LRotr => calc!(u64, rotate_right, masked_shift),
+                    Opcode::IPopcnt => count_bits!(u32, count_ones),
+                    Opcode::LPopcnt => count_bits!(u64, count_ones),
+                    Opcode::IClz => count_bits!(u32, leading_zeros),
+                    Opcode::LClz => count_bits!(u64, leading_zeros),
+                    Opcode::ICtz => count_bits!(u32, trailing_zeros),
+                    Opcode::LCtz => count_bits!(u64, trailing_zeros),
+                    // spec: オフセットと長さはバイト単位; 同一配列内の重なった領域も可
+                    Opcode::ArrayCopy => {
+                        let len = stack_pop!(usize);
+                        let src_off = stack_pop!(usize);
+                        let src_ptr = stack_pop!(*mut c_void);
+                        let dst_off = stack_pop!(usize);
+                        let dst_ptr = stack_pop!(*mut c_void);
+                        let src_size = *(src_ptr as *mut usize);
+                        let dst_size = *(dst_ptr as *mut usize);
+
+                        match (src_off.checked_add(len), dst_off.checked_add(len)) {
+                            (Some(src_end), Some(dst_end)) if src_end <= src_size && dst_end <= dst_size => (),
+                            _ => exit!(ArrayAccessViolation),
+                        }
+
+                        charge_fuel!(len.div_ceil(8));
+
+                        let src_top_ptr = (src_ptr as *mut usize).add(1) as *mut u8;
+                        let dst_top_ptr = (dst_ptr as *mut usize).add(1) as *mut u8;
+                        std::ptr::copy(src_top_ptr.add(src_off), dst_top_ptr.add(dst_off), len);
+
+                        // note: コピーした範囲に配列参照が含まれる可能性があるため、コピー先の 32 ビット語をエスケープ扱いにする
+                        // spec: 参照はバイト単位で格納されうるため、コピー先の範囲に重なる全てのバイト位置の語を escape_word! と同じ基準で検査
+                        if config.auto_drop_arrays && !owned_arrs.is_empty() && len != 0 {
+                            let word_size = size_of::<u32>();
+                            let mut copied_words = HashSet::<u32>::new();
+                            let mut word_off = dst_off.saturating_sub(word_size - 1);
+
+                            while word_off + word_size <= (dst_off + len + word_size - 1).min(dst_size) {
+                                copied_words.insert(std::ptr::read_unaligned(dst_top_ptr.add(word_off) as *const u32));
+                                word_off += 1;
+                            }
+
+                            owned_arrs.retain(|(_, each_ptr)| !copied_words.contains(&(*each_ptr as usize as u32)));
+                        }
+
+                        trace!("{}", format!("[copy {} bytes / src offset {} / dst offset {}]", len, src_off, dst_off).bright_green().dimmed());
+                        trace!();
+                    },
+                    Opcode::BAFill => fill_arr!(u8, u32),
+                    Opcode::SAFill => fill_arr!(u16, u32),
+                    Opcode::IAFill => fill_arr!(u32, u32),
+                    Opcode::LAFill => fill_arr!(u64, u64),
+                    // spec: 符号付き整数として変換し、表現できない値は最近接偶数に丸める
+                    Opcode::I2F => convert!(u32, f32, i32),
+                    Opcode::F2I => float_to_int!(f32, u32, i32),
+                    Opcode::L2D => convert!(u64, f64, i64),
+                    Opcode::D2L => float_to_int!(f64, u64, i64),
+                    Opcode::FCmpL => float_cmp!(f32, -1),
+                    Opcode::FCmpG => float_cmp!(f32, 1),
+                    Opcode::DCmpL => float_cmp!(f64, -1),
+                    Opcode::DCmpG => float_cmp!(f64, 1),
+                    Opcode::IAddSat => calc!(u32, saturating_add, saturating),
+                    Opcode::LAddSat => calc!(u64, saturating_add, saturating),
+                    Opcode::ISubSat => calc!(u32, saturating_sub, saturating),
+                    Opcode::LSubSat => calc!(u64, saturating_sub, saturating),
+                    Opcode::IMulSat => calc!(u32, saturating_mul, saturating),
+                    Opcode::LMulSat => calc!(u64, saturating_mul, saturating),
+                    Opcode::IALoadAtomic => atomic_arr!(u32, AtomicU32, load),
+                    Opcode::LALoadAtomic => atomic_arr!(u64, AtomicU64, load),
+                    Opcode::IAStoreAtomic => atomic_arr!(u32, AtomicU32, store),
+                    Opcode::LAStoreAtomic => atomic_arr!(u64, AtomicU64, store),
+                    Opcode::IACas => atomic_arr!(u32, AtomicU32, compare_exchange),
+                    Opcode::LACas => atomic_arr!(u64, AtomicU64, compare_exchange),
+                    Opcode::IAFetchAdd => atomic_arr!(u32, AtomicU32, fetch_add),
+                    Opcode::LAFetchAdd => atomic_arr!(u64, AtomicU64, fetch_add),
+                    Opcode::GLoad => {
+                        let global_i = next_prg!(u16);
+                        let value = *global_ptr!(u32, global_i);
+                        stack_push!(u32, value);
+                    },
+                    Opcode::GLoad2 => {
+                        let global_i = next_prg!(u16);
+                        let value = std::ptr::read_unaligned(global_ptr!(u64, global_i));
+                        stack_push!(u64, value);
+                    },
+                    Opcode::GStore => {
+                        let global_i = next_prg!(u16);
+                        let value = stack_pop!(u32);
+                        *global_ptr!(u32, global_i) = value;
+                        escape_arr!(value);
+                    },
+                    // spec: 配列を格納した場合はエスケープとして扱う
+                    Opcode::GStore2 => {
+                        let global_i = next_prg!(u16);
+                        let value = stack_pop!(u64);
+                        std::ptr::write_unaligned(global_ptr!(u64, global_i), value);
+                        escape_arr!(value);
+                    },
+                    // spec: 8/16 ビットのフィールドはゼロ拡張して 32 ビット値としてプッシュ
+                    Opcode::GetField => {
+                        let pool_i = next_prg!(usize);
+                        let field_i = next_prg!(u16);
+                        let (_, offset, width) = field_layout!(pool_i, field_i);
+                        let obj_ptr = stack_pop!(*mut c_void);
+                        let field_ptr = obj_field_ptr!(obj_ptr, offset, width);
+
+                        match width {
+                            1 => stack_push!(u32, *field_ptr as u32),
+                            2 => stack_push!(u32, std::ptr::read_unaligned(field_ptr as *const u16) as u32),
+                            4 => stack_push!(u32, std::ptr::read_unaligned(field_ptr as *const u32)),
+                            _ => stack_push!(u64, std::ptr::read_unaligned(field_ptr as *const u64)),
+                        }
+
+                        trace!("{}", format!("[pool index 0x{:0x} / field {} / offset {} / width {}]", pool_i, field_i, offset, width).bright_green().dimmed());
+                        trace!();
+                    },
+                    Opcode::PutField => {
+                        let pool_i = next_prg!(usize);
+                        let field_i = next_prg!(u16);
+                        let (_, offset, width) = field_layout!(pool_i, field_i);
+
+                        match width {
+                            1 => {
+                                let value = narrow!(stack_pop!(u32), u8);
+                                *obj_field_ptr!(stack_pop!(*mut c_void), offset, width) = value;
+                            },
+                            2 => {
+                                let value = narrow!(stack_pop!(u32), u16);
+                                std::ptr::write_unaligned(obj_field_ptr!(stack_pop!(*mut c_void), offset, width) as *mut u16, value);
+                            },
+                            4 => {
+                                let value = stack_pop!(u32);
+                                std::ptr::write_unaligned(obj_field_ptr!(stack_pop!(*mut c_void), offset, width) as *mut u32, value);
+                                escape_arr!(value);
+                            },
+                            _ => {
+                                let value = stack_pop!(u64);
+                                std::ptr::write_unaligned(obj_field_ptr!(stack_pop!(*mut c_void), offset, width) as *mut u64, value);
+                                escape_arr!(value);
+                            },
+                        }
+
+                        trace!("{}", format!("[pool index 0x{:0x} / field {} / offset {} / width {}]", pool_i, field_i, offset, width).bright_green().dimmed());
+                        trace!();
+                    },
+                    // spec: レイアウトのプール要素で指定されたサイズを確保し、0 で初期化
+                    Opcode::New => {
+                        let pool_i = next_prg!(usize);
+                        jump_pool_to!(pool_i);
+                        let obj_size = next_pool!(u32) as usize;
+
+                        let obj_ptr = alloc_arr!(obj_size);
+                        std::ptr::write_bytes((obj_ptr as *mut usize).add(1) as *mut u8, 0, obj_size);
+                        stack_push!(*mut c_void, obj_ptr);
+
+                        trace!("{}", format!("[pool index 0x{:0x} / {} byte size]", pool_i, obj_size).bright_green().dimmed());
+                        trace!();
+                    },
+                    Opcode::ICmp => int_cmp!(u32),
+                    Opcode::LCmp => int_cmp!(u64),
+                    // spec: 128 ビット値は 16 バイトの 1 要素としてスタックに置く
+                    Opcode::QPush => stack_push_next_prg!(u128, u128),
+                    Opcode::QAdd => calc!(u128, overflowing_add),
+                    Opcode::QSub => calc!(u128, overflowing_sub),
+                    Opcode::QMul => calc!(u128, overflowing_mul),
+                    Opcode::QDiv => calc!(u128, overflowing_div, true),
+                    Opcode::IVAdd => vector_calc!(u32, |left_term, right_term| left_term.wrapping_add(right_term)),
+                    Opcode::LVAdd => vector_calc!(u64, |left_term, right_term| left_term.wrapping_add(right_term)),
+                    Opcode::FVAdd => vector_calc!(f32, |left_term, right_term| left_term + right_term),
+                    Opcode::DVAdd => vector_calc!(f64, |left_term, right_term| left_term + right_term),
+                    Opcode::IVMul => vector_calc!(u32, |left_term, right_term| left_term.wrapping_mul(right_term)),
+                    Opcode::LVMul => vector_calc!(u64, |left_term, right_term| left_term.wrapping_mul(right_term)),
+                    Opcode::FVMul => vector_calc!(f32, |left_term, right_term| left_term * right_term),
+                    Opcode::DVMul => vector_calc!(f64, |left_term, right_term| left_term * right_term),
+                    Opcode::IVEq => vector_calc!(u32, |left_term, right_term| (left_term == right_term) as u32),
+                    Opcode::LVEq => vector_calc!(u64, |left_term, right_term| (left_term == right_term) as u64),
+                    // spec: クロージャは関数のプールインデックス (usize) とキャプチャした値 (各 u32) を持つ配列
+                    // spec: キャプチャは値のコピー (変更を共有する場合は配列参照をキャプチャ)
+                    Opcode::MakeClosure => {
+                        let pool_i = next_prg!(usize);
+                        let capture_len = next_prg!(u8) as usize;
+
+                        if sp < bp + frame_header_size + capture_len * size_of::<u32>() {
+                            exit!(StackAccessViolation);
+                        }
+
+                        let mut captures = Vec::<u32>::new();
+
+                        for i in 0..capture_len {
+                            captures.push(std::ptr::read_unaligned((stack_ptr as *mut u32).sub(capture_len - i)));
+                        }
+
+                        stack_pop!(u32, capture_len);
+
+                        let closure_ptr = alloc_arr!(size_of::<usize>() + capture_len * size_of::<u32>());
+                        let closure_top_ptr = (closure_ptr as *mut usize).add(1);
+                        *closure_top_ptr = pool_i;
+                        copy_nonoverlapping(captures.as_ptr(), closure_top_ptr.add(1) as *mut u32, capture_len);
+
+                        // note: キャプチャした値に配列参照が含まれる可能性があるためエスケープ扱いにする
+                        for each_capture in &captures {
+                            escape_arr!(*each_capture);
+                        }
+
+                        stack_push!(*mut c_void, closure_ptr);
+
+                        trace!("{}", format!("[pool index 0x{:0x} / {} captures]", pool_i, capture_len).bright_green().dimmed());
+                        trace!();
+                    },
+                    // spec: キャプチャした値は呼び出し時の引数の後ろに続けて渡す (arg_len はキャプチャ数を含む)
+                    // spec: 先頭の関数プールインデックスは FuncRef か MakeClosure で参照される関数に限る (MakeClosure で作成されていない配列の内容で呼び出し先を決めないように)
+                    Opcode::InvokeClosure => {
+                        let closure_ptr = stack_pop!(*mut usize);
+                        let closure_size = *closure_ptr;
+
+                        if closure_size < size_of::<usize>() || !(closure_size - size_of::<usize>()).is_multiple_of(size_of::<u32>()) {
+                            exit!(ArrayAccessViolation);
+                        }
+
+                        let pool_i = *closure_ptr.add(1);
+
+                        if !indirect_call_targets.contains(&pool_i) {
+                            descriptor_error!(InvalidFunctionDescriptor, pool_i, "indirect call target", "function referenced by FuncRef or MakeClosure".to_string(), format!("0x{:0x}", pool_i));
+                        }
+                        let captures_ptr = closure_ptr.add(2) as *mut u32;
+
+                        for i in 0..(closure_size - size_of::<usize>()) / size_of::<u32>() {
+                            stack_push!(u32, *captures_ptr.add(i));
+                        }
+
+                        invoke!(pool_i);
+                    },
+                    // spec: カナリア (u64) をオペランドスタックにプッシュし、CanaryCheck 時に同じ位置の値と照合
+                    // spec: カナリアはフレームごとに 1 つ (再度 CanarySet した場合は置き換え)
+                    Opcode::CanarySet => {
+                        rng_state ^= rng_state << 13;
+                        rng_state ^= rng_state >> 7;
+                        rng_state ^= rng_state << 17;
+
+                        let canary_value = rng_state;
+                        let canary_offset = sp - bp;
+                        stack_push!(u64, canary_value);
+
+                        if let Some(frame_info) = frame_infos.last_mut() {
+                            frame_info.canary = Some((canary_offset, canary_value));
+                        }
+                    },
+                    Opcode::CanaryCheck => {
+                        let canary = frame_infos.last().and_then(|frame_info| frame_info.canary);
+
+                        match canary {
+                            Some((canary_offset, canary_value)) if bp + canary_offset + size_of::<u64>() <= sp => {
+                                let canary_ptr = stack_ptr.sub(sp - bp - canary_offset) as *mut u64;
+
+                                if std::ptr::read_unaligned(canary_ptr) != canary_value {
+                                    exit!(StackCorruption);
+                                }
+                            },
+                            _ => exit!(StackCorruption),
+                        }
+                    },
+                    // spec: レシーバ (オブジェクト参照) はスタック最上位に置かれた最後の引数とし、ポップせずに参照
+                    // spec: オブジェクトの先頭 8 バイトは vtable のプールインデックス
+                    // spec: vtable のプール要素はメソッド数 (u16) と各メソッドの関数プールインデックス (usize); vtable はヘッダの一覧に含まれるものに限る
+                    Opcode::InvokeVirtual => {
+                        let method_i = next_prg!(u16) as usize;
+                        let obj_ptr = stack_top!(*mut usize);
+
+                        if *obj_ptr < size_of::<usize>() {
+                            exit!(ArrayAccessViolation);
+                        }
+
+                        let vtable_pool_i = *obj_ptr.add(1);
+
+                        if !vtables.contains(&vtable_pool_i) {
+                            descriptor_error!(InvalidLayoutDescriptor, vtable_pool_i, "vtable", "pool index in the vtable list".to_string(), format!("0x{:0x}", vtable_pool_i));
+                        }
+
+                        jump_pool_to!(vtable_pool_i);
+                        let method_len = next_pool!(u16) as usize;
+
+                        if method_i >= method_len {
+                            descriptor_error!(InvalidLayoutDescriptor, vtable_pool_i, "method_len", format!("more than {}", method_i), method_len.to_string());
+                        }
+
+                        jump_to!(pool_ptr, pp, pp + method_i * size_of::<usize>(), bytecode_len, BytecodeAccessViolation);
+                        let pool_i = next_pool!(usize);
+
+                        trace!("{}", format!("[vtable pool index 0x{:0x} / method {}]", vtable_pool_i, method_i).bright_green().dimmed());
+                        invoke!(pool_i);
+                    },
+                    // spec: 配列の内容を定数領域に登録し、ハンドル (u32) をプッシュ (元の配列はそのまま)
+                    Opcode::Intern => {
+                        let arr_ptr = stack_pop!(*mut usize);
+                        let bytes = std::slice::from_raw_parts(arr_ptr.add(1) as *const u8, *arr_ptr);
+
+                        let handle = match constants.intern(bytes, &mut heap_size) {
+                            Some(v) => v,
+                            None => exit!(HeapLimitExceeded),
+                        };
+                        stack_push!(u32, handle);
+
+                        trace!("{}", format!("[handle {}]", handle).bright_green().dimmed());
+                        trace!();
+                    },
+                    // spec: 定数領域の配列は共有されるため書き換えないこと
+                    Opcode::LdInterned => {
+                        let handle = stack_pop!(u32);
+
+                        let const_ptr = match constants.get(handle) {
+                            Some(v) => v,
+                            None => exit!(ArrayAccessViolation),
+                        };
+
+                        stack_push!(*mut c_void, const_ptr);
+
+                        trace!("{}", format!("[handle {} / {} byte size]", handle, *(const_ptr as *mut usize)).bright_green().dimmed());
+                        trace!();
+                    },
                     Opcode::Unknown => exit!(UnknownOpcode),
                 }
             }
         }
 
-        let exit_status_msg = format!("exit status 0x{:0x} ({})", es, ExitStatus::from(es).to_string());
+        #[cfg(feature = "trace")]
+        {
+            let exit_status_msg = format!("exit status 0x{:0x} ({})", es, ExitStatus::from(es).to_string());
 
-        println!("{}", if es == 0 {
-            exit_status_msg.on_bright_black()
-        } else {
-            exit_status_msg.on_red()
-        });
+            println!("{}", if es == 0 {
+                exit_status_msg.on_bright_black()
+            } else {
+                exit_status_msg.on_red()
+            });
+        }
+
+        if config.record_opcode_histogram {
+            let mut sorted_counts = Vec::<(u8, usize)>::new();
+
+            for (opcode, count) in opcode_counts.iter().enumerate() {
+                if *count != 0 {
+                    sorted_counts.push((opcode as u8, *count));
+                }
+            }
+
+            sorted_counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+            #[cfg(feature = "trace")]
+            {
+                let total_count = opcode_counts.iter().sum::<usize>();
+                println!("{}", format!("opcode histogram ({} dispatches)", total_count).bright_black());
+
+                for (opcode, count) in &sorted_counts {
+                    println!("{}", format!("{:<16}{:>12}{:>9.2}%", Opcode::from(*opcode).to_string(), count, *count as f64 * 100.0 / total_count as f64).bright_black());
+                }
+
+                println!();
+            }
+
+            for (opcode, count) in sorted_counts {
+                report.opcode_counts.insert(Opcode::from(opcode).to_string(), count);
+            }
+        }
+
+        // note: 終了時に残っているフレームも計測結果に反映
+        while !frame_infos.is_empty() {
+            pop_frame_info!();
+        }
+
+        for each_const in constants.ptrs {
+            free(each_const);
+        }
+
+        for each_fd in file_handles.into_iter().flatten() {
+            close(each_fd);
+        }
 
         free(stack_ptr.sub(sp));
 
-        return ExitStatus::from(es);
+        return (ExitStatus::from(es), report);
     }
 }
@@ -0,0 +1,88 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+fn int_unary(opcode: Opcode, value: u32, config: VmConfig) -> (ExitStatus, Option<u32>) {
+    let mut asm = Asm::new();
+    asm.ipush(value);
+    asm.op(opcode);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), config);
+    return (status, records.first().map(|bytes| as_u32(bytes)));
+}
+
+fn long_unary(opcode: Opcode, value: u64, config: VmConfig) -> (ExitStatus, Option<u64>) {
+    let mut asm = Asm::new();
+    asm.lpush(value);
+    asm.op(opcode);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), config);
+    return (status, records.first().map(|bytes| as_u64(bytes)));
+}
+
+fn int_binary(opcode: Opcode, left: u32, right: u32) -> u32 {
+    let mut asm = Asm::new();
+    asm.ipush(left);
+    asm.ipush(right);
+    asm.op(opcode);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u32(&records[0]);
+}
+
+fn long_binary(opcode: Opcode, left: u64, right: u64) -> u64 {
+    let mut asm = Asm::new();
+    asm.lpush(left);
+    asm.lpush(right);
+    asm.op(opcode);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u64(&records[0]);
+}
+
+#[test]
+fn negation() {
+    assert_eq!(int_unary(Opcode::INeg, 5, VmConfig::new()), (ExitStatus::Success, Some(-5i32 as u32)));
+    assert_eq!(int_unary(Opcode::IAbs, -5i32 as u32, VmConfig::new()), (ExitStatus::Success, Some(5)));
+    assert_eq!(long_unary(Opcode::LNeg, -7i64 as u64, VmConfig::new()), (ExitStatus::Success, Some(7)));
+    assert_eq!(long_unary(Opcode::LAbs, 0, VmConfig::new()), (ExitStatus::Success, Some(0)));
+}
+
+#[test]
+fn negation_of_minimum_overflows() {
+    for opcode in [Opcode::INeg, Opcode::IAbs] {
+        assert_eq!(int_unary(opcode, i32::MIN as u32, VmConfig::new()), (ExitStatus::ArithmeticOverflow, None));
+    }
+
+    for opcode in [Opcode::LNeg, Opcode::LAbs] {
+        assert_eq!(long_unary(opcode, i64::MIN as u64, VmConfig::new()), (ExitStatus::ArithmeticOverflow, None));
+    }
+
+    // note: トラップしない場合は最小値のまま
+    let config = VmConfig::new().with_trap_on_overflow(false);
+    assert_eq!(int_unary(Opcode::INeg, i32::MIN as u32, config.clone()), (ExitStatus::Success, Some(i32::MIN as u32)));
+    assert_eq!(int_unary(Opcode::IAbs, i32::MIN as u32, config.clone()), (ExitStatus::Success, Some(i32::MIN as u32)));
+    assert_eq!(long_unary(Opcode::LNeg, i64::MIN as u64, config), (ExitStatus::Success, Some(i64::MIN as u64)));
+}
+
+#[test]
+fn min_max_compare_unsigned() {
+    // note: IOrd と同様に符号なしで比較
+    assert_eq!(int_binary(Opcode::IMin, i32::MAX as u32, i32::MIN as u32), i32::MAX as u32);
+    assert_eq!(int_binary(Opcode::IMax, i32::MAX as u32, i32::MIN as u32), i32::MIN as u32);
+    assert_eq!(int_binary(Opcode::IMin, 0, u32::MAX), 0);
+    assert_eq!(int_binary(Opcode::IMax, 7, 7), 7);
+    assert_eq!(long_binary(Opcode::LMin, u64::MAX, 1), 1);
+    assert_eq!(long_binary(Opcode::LMax, i64::MIN as u64, i64::MAX as u64), i64::MIN as u64);
+}
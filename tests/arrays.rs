@@ -0,0 +1,142 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: len 要素の配列を確保して value で埋め、配列参照を残す
+fn filled_int_array(asm: &mut Asm, len: u64, value: u32) {
+    asm.op(Opcode::IAPush).u64(len);
+    asm.op(Opcode::Dup2);
+    asm.ipush(value);
+    asm.lpush(len);
+    asm.op(Opcode::IAFill);
+}
+
+// note: スタック最上位の配列の arr_i 番目の要素を記録 (配列参照は残す)
+fn record_int_element(asm: &mut Asm, arr_i: u64) {
+    asm.op(Opcode::Dup2);
+    asm.lpush(arr_i);
+    asm.op(Opcode::IALoad);
+    asm.call(RECORD_U32);
+}
+
+#[test]
+fn fill_sets_every_element() {
+    let mut asm = Asm::new();
+    filled_int_array(&mut asm, 4, 7);
+    record_int_element(&mut asm, 0);
+    record_int_element(&mut asm, 3);
+    asm.op(Opcode::LAPush).u64(2);
+    asm.op(Opcode::Dup2);
+    asm.lpush(u64::MAX);
+    asm.lpush(2);
+    asm.op(Opcode::LAFill);
+    asm.lpush(1);
+    asm.op(Opcode::LALoad);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u32(&records[0]), 7);
+    assert_eq!(as_u32(&records[1]), 7);
+    assert_eq!(as_u64(&records[2]), u64::MAX);
+}
+
+#[test]
+fn fill_past_bounds_is_rejected() {
+    // note: 要素数を 1 つ超える場合と、バイトサイズが usize に収まらない場合
+    for len in [5, u64::MAX] {
+        let mut asm = Asm::new();
+        asm.op(Opcode::IAPush).u64(4);
+        asm.ipush(7);
+        asm.lpush(len);
+        asm.op(Opcode::IAFill);
+        asm.op(Opcode::Exit);
+
+        assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::ArrayAccessViolation);
+    }
+
+    // note: 0 要素は空の配列でも可
+    let mut asm = Asm::new();
+    asm.op(Opcode::IAPush).u64(0);
+    asm.ipush(7);
+    asm.lpush(0);
+    asm.op(Opcode::IAFill);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::Success);
+}
+
+#[test]
+fn fill_value_follows_checked_narrowing() {
+    for (checked_narrowing, expected_status) in [(false, ExitStatus::Success), (true, ExitStatus::NarrowingOverflow)] {
+        let mut asm = Asm::new();
+        asm.op(Opcode::SAPush).u64(2);
+        asm.ipush(0x1_0000);
+        asm.lpush(2);
+        asm.op(Opcode::SAFill);
+        asm.op(Opcode::Exit);
+
+        let config = VmConfig::new().with_checked_narrowing(checked_narrowing);
+        assert_eq!(run_recorded(&asm.build(), config).0, expected_status);
+    }
+}
+
+#[test]
+fn vector_ops_apply_element_wise() {
+    // note: dst (要素 0) に left (u32::MAX) + right (2) を書き込み、整数はラップアラウンド
+    let mut asm = Asm::new();
+    filled_int_array(&mut asm, 4, 0);
+    asm.op(Opcode::Dup2);
+    filled_int_array(&mut asm, 4, u32::MAX);
+    filled_int_array(&mut asm, 4, 2);
+    asm.lpush(4);
+    asm.op(Opcode::IVAdd);
+    record_int_element(&mut asm, 3);
+
+    // note: dst は left と同一の配列でも可
+    asm.op(Opcode::Dup2);
+    asm.op(Opcode::Dup2);
+    filled_int_array(&mut asm, 4, 1);
+    asm.lpush(4);
+    asm.op(Opcode::IVEq);
+    record_int_element(&mut asm, 0);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u32(&records[0]), 1);
+    assert_eq!(as_u32(&records[1]), 1);
+}
+
+#[test]
+fn vector_ops_past_bounds_are_rejected() {
+    // note: いずれかの配列が len 要素に満たない場合 (dst は変更しない)
+    for short_i in 0..3 {
+        let mut asm = Asm::new();
+
+        for arr_i in 0..3 {
+            filled_int_array(&mut asm, if arr_i == short_i { 3 } else { 4 }, 1);
+        }
+
+        asm.lpush(4);
+        asm.op(Opcode::IVMul);
+        asm.op(Opcode::Exit);
+
+        assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::ArrayAccessViolation);
+    }
+
+    // note: バイトサイズが usize に収まらない要素数
+    let mut asm = Asm::new();
+
+    for _ in 0..3 {
+        filled_int_array(&mut asm, 1, 1);
+    }
+
+    asm.lpush(u64::MAX);
+    asm.op(Opcode::LVAdd);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::ArrayAccessViolation);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 要素 5 の 1 要素の配列に対して IACas を実行し、直前の値と実行後の値を記録
+fn cas(expected: u32) -> (ExitStatus, Vec<Vec<u8>>) {
+    let mut asm = Asm::new();
+    asm.op(Opcode::IAPush).u64(1);
+    asm.op(Opcode::Dup2);
+    asm.lpush(0);
+    asm.ipush(5);
+    asm.op(Opcode::IAStoreAtomic);
+
+    asm.op(Opcode::Dup2);
+    asm.lpush(0);
+    asm.ipush(expected);
+    asm.ipush(9);
+    asm.op(Opcode::IACas);
+    asm.call(RECORD_U32);
+
+    asm.lpush(0);
+    asm.op(Opcode::IALoadAtomic);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    return run_recorded(&asm.build(), VmConfig::new());
+}
+
+#[test]
+fn cas_swaps_only_on_match() {
+    let (status, records) = cas(5);
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!((as_u32(&records[0]), as_u32(&records[1])), (5, 9));
+
+    // note: 失敗時も直前の値をプッシュし、要素は変更しない
+    let (status, records) = cas(4);
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!((as_u32(&records[0]), as_u32(&records[1])), (5, 5));
+}
+
+#[test]
+fn long_cas_and_fetch_add() {
+    let mut asm = Asm::new();
+    asm.op(Opcode::LAPush).u64(1);
+    asm.op(Opcode::Dup2);
+    asm.lpush(0);
+    asm.lpush(u64::MAX);
+    asm.op(Opcode::LAStoreAtomic);
+
+    // note: 加算はラップアラウンド
+    asm.op(Opcode::Dup2);
+    asm.lpush(0);
+    asm.lpush(2);
+    asm.op(Opcode::LAFetchAdd);
+    asm.call(RECORD_U64);
+
+    asm.lpush(0);
+    asm.lpush(1);
+    asm.lpush(3);
+    asm.op(Opcode::LACas);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u64(&records[0]), u64::MAX);
+    assert_eq!(as_u64(&records[1]), 1);
+}
+
+#[test]
+fn atomic_access_past_bounds_is_rejected() {
+    let mut asm = Asm::new();
+    asm.op(Opcode::IAPush).u64(1);
+    asm.lpush(1);
+    asm.ipush(0);
+    asm.ipush(1);
+    asm.op(Opcode::IACas);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::ArrayAccessViolation);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 両オペランドを 64 ビット値から多倍長整数に変換して演算し、結果を 64 ビット値に戻して記録
+fn big_int_calc(left: i64, right: i64, call_code: u8) -> (ExitStatus, Vec<Vec<u8>>) {
+    let mut asm = Asm::new();
+    asm.lpush(left as u64);
+    asm.call(0x0d);
+    asm.lpush(right as u64);
+    asm.call(0x0d);
+    asm.call(call_code);
+    asm.call(0x0e);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+    return run_recorded(&asm.build(), VmConfig::new());
+}
+
+#[test]
+fn arithmetic_matches_i64() {
+    let cases = [
+        (0x08, i64::MAX - 1, 1, i64::MAX),
+        (0x09, i64::MIN + 1, 1, i64::MIN),
+        (0x0a, -3_000_000_000, 3, -9_000_000_000),
+        (0x0b, 7, 2, 3),
+    ];
+
+    for (call_code, left, right, expected) in cases {
+        let (status, records) = big_int_calc(left, right, call_code);
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(as_u64(&records[0]) as i64, expected);
+    }
+}
+
+#[test]
+fn division_truncates_toward_zero() {
+    for (left, right, expected) in [(-7, 2, -3), (7, -2, -3), (-7, -2, 3)] {
+        let (status, records) = big_int_calc(left, right, 0x0b);
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(as_u64(&records[0]) as i64, expected);
+    }
+}
+
+#[test]
+fn division_by_zero_traps() {
+    assert_eq!(big_int_calc(7, 0, 0x0b).0, ExitStatus::DivideByZero);
+    assert_eq!(big_int_calc(0, 0, 0x0b).0, ExitStatus::DivideByZero);
+}
+
+#[test]
+fn results_exceeding_i64_are_not_truncated() {
+    // note: 中間結果は 64 ビットを超えても保持され、変換時にのみ検査される
+    assert_eq!(big_int_calc(i64::MAX, 1, 0x08).0, ExitStatus::ArithmeticOverflow);
+    assert_eq!(big_int_calc(i64::MIN, -1, 0x0b).0, ExitStatus::ArithmeticOverflow);
+}
+
+#[test]
+fn compare_pushes_sign() {
+    for (left, right, expected) in [(-5, 3, -1), (3, 3, 0), (i64::MAX, i64::MIN, 1)] {
+        let mut asm = Asm::new();
+        asm.lpush(left as u64);
+        asm.call(0x0d);
+        asm.lpush(right as u64);
+        asm.call(0x0d);
+        asm.call(0x0c);
+        asm.call(RECORD_U32);
+        asm.op(Opcode::Exit);
+
+        let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(as_u32(&records[0]) as i32, expected);
+    }
+}
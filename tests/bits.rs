@@ -0,0 +1,155 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 2 つの 32 ビット値をプッシュして演算し、結果を返す
+fn int_binary(opcode: Opcode, left: u32, right: u32) -> u32 {
+    let mut asm = Asm::new();
+    asm.ipush(left);
+    asm.ipush(right);
+    asm.op(opcode);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u32(&records[0]);
+}
+
+fn long_binary(opcode: Opcode, left: u64, right: u64) -> u64 {
+    let mut asm = Asm::new();
+    asm.lpush(left);
+    asm.lpush(right);
+    asm.op(opcode);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u64(&records[0]);
+}
+
+// note: 64 ビット値と 32 ビットのシフト量をプッシュして演算
+fn long_shift(opcode: Opcode, value: u64, amount: u32) -> u64 {
+    let mut asm = Asm::new();
+    asm.lpush(value);
+    asm.ipush(amount);
+    asm.op(opcode);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u64(&records[0]);
+}
+
+// note: 64 ビット値をプッシュして演算し、32 ビットの結果を返す
+fn long_count(opcode: Opcode, value: u64) -> u32 {
+    let mut asm = Asm::new();
+    asm.lpush(value);
+    asm.op(opcode);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u32(&records[0]);
+}
+
+fn int_unary(opcode: Opcode, value: u32) -> u32 {
+    let mut asm = Asm::new();
+    asm.ipush(value);
+    asm.op(opcode);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u32(&records[0]);
+}
+
+fn long_unary(opcode: Opcode, value: u64) -> u64 {
+    let mut asm = Asm::new();
+    asm.lpush(value);
+    asm.op(opcode);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u64(&records[0]);
+}
+
+#[test]
+fn bitwise_ops() {
+    assert_eq!(int_binary(Opcode::IAnd, 0xf0f0_f0f0, 0xff00_ff00), 0xf000_f000);
+    assert_eq!(int_binary(Opcode::IOr, 0xf0f0_f0f0, 0x0f00_0f00), 0xfff0_fff0);
+    assert_eq!(int_binary(Opcode::IXor, u32::MAX, 0x0f0f_0f0f), 0xf0f0_f0f0);
+    assert_eq!(long_binary(Opcode::LAnd, u64::MAX, 1 << 63), 1 << 63);
+    assert_eq!(long_binary(Opcode::LOr, 1 << 63, 1), (1 << 63) | 1);
+    assert_eq!(long_binary(Opcode::LXor, u64::MAX, u64::MAX), 0);
+    assert_eq!(int_unary(Opcode::INot, 0), u32::MAX);
+    assert_eq!(long_unary(Opcode::LNot, u64::MAX), 0);
+}
+
+#[test]
+fn shift_amount_is_masked() {
+    // note: ビット幅以上のシフト量はビット幅で割った余り
+    assert_eq!(int_binary(Opcode::IShl, 1, 31), 1 << 31);
+    assert_eq!(int_binary(Opcode::IShl, 1, 32), 1);
+    assert_eq!(int_binary(Opcode::IShl, 1, 33), 2);
+    assert_eq!(int_binary(Opcode::IShr, 1 << 31, 32), 1 << 31);
+    assert_eq!(int_binary(Opcode::IShr, 1 << 31, 31), 1);
+    assert_eq!(long_shift(Opcode::LShl, 1, 64), 1);
+    assert_eq!(long_shift(Opcode::LShr, 1 << 63, 65), 1 << 62);
+}
+
+#[test]
+fn arithmetic_shift_keeps_sign() {
+    assert_eq!(int_binary(Opcode::IAShr, 1 << 31, 31), u32::MAX);
+    assert_eq!(int_binary(Opcode::IAShr, 1 << 31, 32), 1 << 31);
+    assert_eq!(int_binary(Opcode::IAShr, 0x4000_0000, 30), 1);
+    assert_eq!(long_shift(Opcode::LAShr, 1 << 63, 63), u64::MAX);
+    assert_eq!(long_shift(Opcode::LAShr, 1 << 63, 64), 1 << 63);
+}
+
+#[test]
+fn rotate() {
+    // note: 0 とビット幅の回転では値は変わらない
+    for amount in [0, 32] {
+        assert_eq!(int_binary(Opcode::IRotl, 0x8000_0001, amount), 0x8000_0001);
+        assert_eq!(int_binary(Opcode::IRotr, 0x8000_0001, amount), 0x8000_0001);
+    }
+
+    for amount in [0, 64] {
+        assert_eq!(long_shift(Opcode::LRotl, 0x8000_0000_0000_0001, amount), 0x8000_0000_0000_0001);
+        assert_eq!(long_shift(Opcode::LRotr, 0x8000_0000_0000_0001, amount), 0x8000_0000_0000_0001);
+    }
+
+    assert_eq!(int_binary(Opcode::IRotl, 0x8000_0001, 1), 3);
+    assert_eq!(int_binary(Opcode::IRotr, 1, 33), 0x8000_0000);
+    assert_eq!(long_shift(Opcode::LRotl, 1 << 63, 1), 1);
+    assert_eq!(long_shift(Opcode::LRotr, 1, 1), 1 << 63);
+}
+
+#[test]
+fn bit_counts_of_zero() {
+    // note: 0 の先頭・末尾の 0 の数はビット幅
+    assert_eq!(int_unary(Opcode::IPopcnt, 0), 0);
+    assert_eq!(int_unary(Opcode::IClz, 0), 32);
+    assert_eq!(int_unary(Opcode::ICtz, 0), 32);
+    assert_eq!(long_count(Opcode::LPopcnt, 0), 0);
+    assert_eq!(long_count(Opcode::LClz, 0), 64);
+    assert_eq!(long_count(Opcode::LCtz, 0), 64);
+}
+
+#[test]
+fn bit_counts() {
+    assert_eq!(int_unary(Opcode::IPopcnt, u32::MAX), 32);
+    assert_eq!(int_unary(Opcode::IClz, 1), 31);
+    assert_eq!(int_unary(Opcode::ICtz, 1 << 31), 31);
+    assert_eq!(long_count(Opcode::LPopcnt, u64::MAX), 64);
+    assert_eq!(long_count(Opcode::LClz, 1 << 32), 31);
+    assert_eq!(long_count(Opcode::LCtz, 1 << 63), 63);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 2 値を比較して分岐したかどうかを返す (分岐した場合は 1 を記録)
+fn branch_taken(opcode: Opcode, push: impl Fn(&mut Asm)) -> bool {
+    let mut asm = Asm::new();
+    push(&mut asm);
+    asm.jump(opcode, "taken");
+    asm.ipush(0);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+    asm.label("taken");
+    asm.ipush(1);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    return as_u32(&records[0]) == 1;
+}
+
+fn int_branch_taken(opcode: Opcode, left: u32, right: u32) -> bool {
+    return branch_taken(opcode, |asm| {
+        asm.ipush(left);
+        asm.ipush(right);
+    });
+}
+
+fn long_branch_taken(opcode: Opcode, left: u64, right: u64) -> bool {
+    return branch_taken(opcode, |asm| {
+        asm.lpush(left);
+        asm.lpush(right);
+    });
+}
+
+#[test]
+fn fused_branches_compare_unsigned() {
+    // note: IOrd と同様に符号なしで比較するため、符号付きの最大値は最小値 (0x80000000) より小さい
+    let (max, min) = (i32::MAX as u32, i32::MIN as u32);
+
+    for (opcode, expected) in [
+        (Opcode::IfICmpLt, true),
+        (Opcode::IfICmpLe, true),
+        (Opcode::IfICmpGt, false),
+        (Opcode::IfICmpGe, false),
+        (Opcode::IfICmpEq, false),
+        (Opcode::IfICmpNe, true),
+    ] {
+        assert_eq!(int_branch_taken(opcode, max, min), expected);
+    }
+
+    assert!(int_branch_taken(Opcode::IfICmpGt, u32::MAX, 0));
+    assert!(long_branch_taken(Opcode::IfLCmpLt, i64::MAX as u64, i64::MIN as u64));
+    assert!(long_branch_taken(Opcode::IfLCmpGt, u64::MAX, 0));
+}
+
+#[test]
+fn fused_branches_at_equal_values() {
+    for (opcode, expected) in [
+        (Opcode::IfICmpLt, false),
+        (Opcode::IfICmpLe, true),
+        (Opcode::IfICmpGt, false),
+        (Opcode::IfICmpGe, true),
+        (Opcode::IfICmpEq, true),
+        (Opcode::IfICmpNe, false),
+    ] {
+        assert_eq!(int_branch_taken(opcode, i32::MIN as u32, i32::MIN as u32), expected);
+    }
+
+    for (opcode, expected) in [(Opcode::IfLCmpLe, true), (Opcode::IfLCmpGe, true), (Opcode::IfLCmpEq, true), (Opcode::IfLCmpNe, false)] {
+        assert_eq!(long_branch_taken(opcode, i64::MIN as u64, i64::MIN as u64), expected);
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 変数 0 (引数) と変数 1 (キャプチャ) の和を記録する関数
+fn adder(asm: &mut Asm) -> u64 {
+    let func_i = asm.func("adder", 2, 2, &[]);
+    asm.label("adder");
+    asm.op(Opcode::Load).u16(0);
+    asm.op(Opcode::Load).u16(1);
+    asm.op(Opcode::IAdd);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Ret);
+    return func_i;
+}
+
+#[test]
+fn captures_follow_arguments() {
+    let mut asm = Asm::new();
+    asm.jump(Opcode::Goto, "entry");
+    let adder_i = adder(&mut asm);
+
+    asm.label("entry");
+    asm.ipush(37);
+    asm.ipush(5);
+    asm.op(Opcode::MakeClosure).u64(adder_i).u8(1);
+    asm.op(Opcode::InvokeClosure);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u32(&records[0]), 42);
+}
+
+#[test]
+fn captured_array_escapes() {
+    // note: 呼び出し先で確保した配列 (変数 0-1) をキャプチャしたクロージャを破棄して戻る
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 2, 0, &[]);
+    asm.invoke(callee_i);
+    asm.op(Opcode::BAPush).u64(100);
+    asm.op(Opcode::Exit);
+
+    asm.label("callee");
+    asm.op(Opcode::BAPush).u64(100);
+    asm.op(Opcode::Store2).u16(0);
+    asm.op(Opcode::Load2).u16(0);
+    asm.op(Opcode::MakeClosure).u64(callee_i).u8(2);
+    asm.op(Opcode::Pop2);
+    asm.op(Opcode::Ret);
+
+    // note: キャプチャされた配列は解放されないため 2 回目の確保で上限を超える
+    let config = VmConfig::new().with_auto_drop_arrays(true).with_max_heap_size(Some(200));
+    assert_eq!(run_recorded(&asm.build(), config).0, ExitStatus::HeapLimitExceeded);
+}
+
+#[test]
+fn non_closure_arrays_are_rejected() {
+    let mut asm = Asm::new();
+    asm.ipush(1);
+    asm.op(Opcode::BAPush).u64(3);
+    asm.op(Opcode::InvokeClosure);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::ArrayAccessViolation);
+}
+
+#[test]
+fn forged_closure_is_rejected() {
+    // note: 関数プールインデックスを書き込んだだけの配列はクロージャとして呼び出せない
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 0, 0, &[]);
+
+    asm.invoke(callee_i);
+    asm.op(Opcode::LAPush).u64(1);
+    asm.op(Opcode::Dup2);
+    asm.lpush(0);
+    asm.lpush(callee_i);
+    asm.op(Opcode::LAStore);
+    asm.op(Opcode::InvokeClosure);
+    asm.op(Opcode::Exit);
+    asm.label("callee");
+    asm.op(Opcode::Ret);
+
+    let (status, report) = Vm::new().run_module_with_report(&asm.build());
+    assert_eq!(status, ExitStatus::InvalidFunctionDescriptor);
+    assert_eq!(report.descriptor_error().map(|error| error.field), Some("indirect call target"));
+}
@@ -0,0 +1,289 @@
+// note: 結合テスト用のバイトコード組み立てと値の記録用ホストコール
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use rustnut::*;
+
+// spec: ヘッダ (128 バイト) | プールテーブル (usize × 要素数) | プール要素 | コード
+// spec: プールインデックス 0 はエントリポイント (コード先頭) を指す
+pub struct Asm {
+    code: Vec<u8>,
+    labels: HashMap<String, usize>,
+    // note: (オペコードの位置, ラベル名) 相対ジャンプ (i16) の参照元
+    jump_fixups: Vec<(usize, String)>,
+    // note: エントリポイント以外のプール要素 (関数要素は開始ラベルを持ち、開始アドレスは組み立て時に埋める)
+    items: Vec<(Option<String>, Vec<u8>)>,
+    global_len: u16,
+    descriptor_flags: u8,
+    // note: インポート表の要素 (名前, 引数のバイトサイズ, 戻り値のバイトサイズ)
+    imports: Vec<(String, u16, u16)>,
+    // note: ヘッダの vtable の一覧に含める vtable のプールインデックス
+    vtables: Vec<u64>,
+}
+
+impl Asm {
+    pub fn new() -> Asm {
+        return Asm {
+            code: Vec::new(),
+            labels: HashMap::new(),
+            jump_fixups: Vec::new(),
+            items: Vec::new(),
+            global_len: 0,
+            descriptor_flags: 0,
+            imports: Vec::new(),
+            vtables: Vec::new(),
+        };
+    }
+
+    pub fn op(&mut self, opcode: Opcode) -> &mut Asm {
+        self.code.push(opcode.into());
+        return self;
+    }
+
+    pub fn u8(&mut self, value: u8) -> &mut Asm {
+        self.code.push(value);
+        return self;
+    }
+
+    pub fn u16(&mut self, value: u16) -> &mut Asm {
+        self.code.extend(&value.to_le_bytes());
+        return self;
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Asm {
+        self.code.extend(&value.to_le_bytes());
+        return self;
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Asm {
+        self.code.extend(&value.to_le_bytes());
+        return self;
+    }
+
+    pub fn u128(&mut self, value: u128) -> &mut Asm {
+        self.code.extend(&value.to_le_bytes());
+        return self;
+    }
+
+    pub fn f32(&mut self, value: f32) -> &mut Asm {
+        self.code.extend(&value.to_le_bytes());
+        return self;
+    }
+
+    pub fn f64(&mut self, value: f64) -> &mut Asm {
+        self.code.extend(&value.to_le_bytes());
+        return self;
+    }
+
+    pub fn ipush(&mut self, value: u32) -> &mut Asm {
+        return self.op(Opcode::IPush).u32(value);
+    }
+
+    pub fn lpush(&mut self, value: u64) -> &mut Asm {
+        return self.op(Opcode::LPush).u64(value);
+    }
+
+    pub fn label(&mut self, name: &str) -> &mut Asm {
+        self.labels.insert(name.to_string(), self.code.len());
+        return self;
+    }
+
+    // spec: オフセットはジャンプ命令の末尾からの相対位置 (i16)
+    pub fn jump(&mut self, opcode: Opcode, label: &str) -> &mut Asm {
+        let opcode_pos = self.code.len();
+        self.op(opcode);
+        self.jump_fixups.push((opcode_pos, label.to_string()));
+        return self.u16(0);
+    }
+
+    // note: 関数プール要素を追加してプールインデックスを返す (extra は arg_len 以降のフィールド)
+    pub fn func(&mut self, label: &str, var_len: u16, arg_len: u8, extra: &[u8]) -> u64 {
+        let mut desc = var_len.to_le_bytes().to_vec();
+        desc.push(arg_len);
+        desc.extend(extra);
+        self.items.push((Some(label.to_string()), desc));
+        return self.items.len() as u64;
+    }
+
+    // note: ラベルのアドレス (usize) のみを持つプール要素を追加してプールインデックスを返す (TryEnter のハンドラなど)
+    pub fn addr(&mut self, label: &str) -> u64 {
+        self.items.push((Some(label.to_string()), Vec::new()));
+        return self.items.len() as u64;
+    }
+
+    // note: 任意のバイト列のプール要素を追加してプールインデックスを返す
+    pub fn item(&mut self, bytes: &[u8]) -> u64 {
+        self.items.push((None, bytes.to_vec()));
+        return self.items.len() as u64;
+    }
+
+    pub fn invoke(&mut self, pool_i: u64) -> &mut Asm {
+        return self.op(Opcode::Invoke).u64(pool_i);
+    }
+
+    pub fn call(&mut self, code: u8) -> &mut Asm {
+        return self.op(Opcode::Call).u8(code);
+    }
+
+    // note: インポート表に要素を追加し、Call で指定するコール番号を返す
+    pub fn import(&mut self, name: &str, arg_size: u16, ret_size: u16) -> u8 {
+        self.imports.push((name.to_string(), arg_size, ret_size));
+        return HOST_CALL_NUMBER_START + (self.imports.len() - 1) as u8;
+    }
+
+    // note: vtable のプール要素を追加して vtable の一覧に含め、プールインデックスを返す
+    pub fn vtable(&mut self, method_pool_indexes: &[u64]) -> u64 {
+        let mut vtable = (method_pool_indexes.len() as u16).to_le_bytes().to_vec();
+
+        for each_pool_i in method_pool_indexes {
+            vtable.extend(&each_pool_i.to_le_bytes());
+        }
+
+        let vtable_i = self.item(&vtable);
+        self.vtables.push(vtable_i);
+        return vtable_i;
+    }
+
+    pub fn globals(&mut self, global_len: u16) -> &mut Asm {
+        self.global_len = global_len;
+        return self;
+    }
+
+    pub fn descriptor_flags(&mut self, flags: u8) -> &mut Asm {
+        self.descriptor_flags = flags;
+        return self;
+    }
+
+    pub fn build(&self) -> Module {
+        return Module::from_bytes(self.build_bytes());
+    }
+
+    pub fn build_bytes(&self) -> Vec<u8> {
+        let mut code = self.code.clone();
+
+        for (opcode_pos, label) in &self.jump_fixups {
+            let offset = (self.labels[label] as isize - (*opcode_pos + 3) as isize) as i16;
+            code[opcode_pos + 1..opcode_pos + 3].copy_from_slice(&offset.to_le_bytes());
+        }
+
+        let mut items = self.items.clone();
+
+        // note: インポート表は最後のプール要素として追加
+        if !self.imports.is_empty() {
+            let mut table = vec![self.imports.len() as u8];
+
+            for (name, arg_size, ret_size) in &self.imports {
+                table.push(name.len() as u8);
+                table.extend(name.as_bytes());
+                table.extend(&arg_size.to_le_bytes());
+                table.extend(&ret_size.to_le_bytes());
+            }
+
+            items.push((None, table));
+        }
+
+        let import_pool_i = items.len();
+
+        // note: vtable の一覧はインポート表の後ろに追加
+        if !self.vtables.is_empty() {
+            let mut list = (self.vtables.len() as u16).to_le_bytes().to_vec();
+
+            for each_vtable_i in &self.vtables {
+                list.extend(&each_vtable_i.to_le_bytes());
+            }
+
+            items.push((None, list));
+        }
+
+        let mut item_addr = 128 + (1 + items.len()) * 8;
+        let mut addrs = Vec::<usize>::new();
+
+        // note: エントリポイント (usize)
+        addrs.push(item_addr);
+        item_addr += 8;
+
+        for (label, each_item) in &items {
+            addrs.push(item_addr);
+            item_addr += if label.is_some() { 8 } else { 0 } + each_item.len();
+        }
+
+        let code_start = item_addr;
+
+        let mut bytes = vec![0u8; 128];
+        bytes[0..8].copy_from_slice(b"CHESCCBC");
+        bytes[16..19].copy_from_slice(&[CURRENT_CHES_VERSION.0 as u8, CURRENT_CHES_VERSION.1 as u8, CURRENT_CHES_VERSION.2 as u8]);
+        bytes[19..21].copy_from_slice(&self.global_len.to_le_bytes());
+        bytes[21] = self.descriptor_flags;
+
+        if !self.imports.is_empty() {
+            bytes[22..30].copy_from_slice(&(import_pool_i as u64).to_le_bytes());
+        }
+
+        if !self.vtables.is_empty() {
+            bytes[30..38].copy_from_slice(&(items.len() as u64).to_le_bytes());
+        }
+
+        for each_addr in &addrs {
+            bytes.extend(&(*each_addr as u64).to_le_bytes());
+        }
+
+        bytes.extend(&(code_start as u64).to_le_bytes());
+
+        for (label, each_item) in &items {
+            if let Some(label) = label {
+                bytes.extend(&((code_start + self.labels[label]) as u64).to_le_bytes());
+            }
+
+            bytes.extend(each_item);
+        }
+
+        bytes.extend(&code);
+        return bytes;
+    }
+}
+
+// spec: 記録用ホストコールの番号 (引数のバイト列をそのまま記録し、何もプッシュしない)
+pub const RECORD_U32: u8 = 0x80;
+pub const RECORD_U64: u8 = 0x81;
+pub const RECORD_U128: u8 = 0x82;
+
+// note: 記録先は実行時に渡す user_data (Vec<Vec<u8>>)
+fn record(context: &mut HostContext, args: &[u8]) -> Option<Vec<u8>> {
+    context.user_data::<Vec<Vec<u8>>>()?.push(args.to_vec());
+    return Some(Vec::new());
+}
+
+pub fn with_recorders(mut config: VmConfig) -> VmConfig {
+    for (name, number, arg_size) in [("record_u32", RECORD_U32, 4), ("record_u64", RECORD_U64, 8), ("record_u128", RECORD_U128, 16)] {
+        config.register_host_call(HostCall {
+            name: name.to_string(),
+            number: number,
+            arg_size: arg_size,
+            ret_size: 0,
+            handler: record,
+        }).unwrap();
+    }
+
+    return config;
+}
+
+// note: モジュールを実行し、終了ステータスと記録された値を返す
+pub fn run_recorded(module: &Module, config: VmConfig) -> (ExitStatus, Vec<Vec<u8>>) {
+    let mut records = Vec::<Vec<u8>>::new();
+    let (status, _) = Vm::with_config(with_recorders(config)).run_module_with_user_data(module, &mut records);
+    return (status, records);
+}
+
+pub fn as_u32(bytes: &[u8]) -> u32 {
+    return u32::from_le_bytes(bytes.try_into().unwrap());
+}
+
+pub fn as_u64(bytes: &[u8]) -> u64 {
+    return u64::from_le_bytes(bytes.try_into().unwrap());
+}
+
+pub fn as_u128(bytes: &[u8]) -> u128 {
+    return u128::from_le_bytes(bytes.try_into().unwrap());
+}
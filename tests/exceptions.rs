@@ -0,0 +1,112 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 空の関数を呼び出した後に例外を投げ、呼び出し元で登録したハンドラが捕捉すること
+fn throw_after_call(config: VmConfig) -> (ExitStatus, Vec<Vec<u8>>) {
+    let mut asm = Asm::new();
+    let handler_i = asm.addr("handler");
+    let empty_i = asm.func("empty", 0, 0, &[]);
+
+    asm.op(Opcode::TryEnter).u64(handler_i);
+    asm.invoke(empty_i);
+    asm.ipush(0x2a);
+    asm.op(Opcode::Throw);
+    asm.op(Opcode::Exit);
+
+    asm.label("handler");
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    asm.label("empty");
+    asm.op(Opcode::Ret);
+
+    return run_recorded(&asm.build(), config);
+}
+
+#[test]
+fn handler_survives_callee_return() {
+    for config in [VmConfig::new(), VmConfig::new().with_side_return_stack(true)] {
+        let (status, records) = throw_after_call(config);
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(records.len(), 1);
+        assert_eq!(as_u32(&records[0]), 0x2a);
+    }
+}
+
+#[test]
+fn handler_runs_after_call_instruction_limit() {
+    let mut asm = Asm::new();
+    let handler_i = asm.addr("handler");
+
+    asm.op(Opcode::TryEnter).u64(handler_i);
+    asm.label("loop");
+    asm.jump(Opcode::Goto, "loop");
+
+    asm.label("handler");
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let config = VmConfig::new().with_max_call_instructions(Some(16));
+    let (status, records) = run_recorded(&asm.build(), config);
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u32(&records[0]), ExitStatus::InstructionLimitExceeded as u32);
+}
+
+// note: ハンドラを登録してから emit の命令で終了させ、ハンドラに渡された値を記録
+fn trap_in_try(emit: impl Fn(&mut Asm), config: VmConfig) -> (ExitStatus, Vec<Vec<u8>>) {
+    let mut asm = Asm::new();
+    let handler_i = asm.addr("handler");
+
+    asm.op(Opcode::TryEnter).u64(handler_i);
+    emit(&mut asm);
+    asm.op(Opcode::Exit);
+
+    asm.label("handler");
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    return run_recorded(&asm.build(), config);
+}
+
+#[test]
+fn arithmetic_traps_are_catchable() {
+    let (status, records) = trap_in_try(|asm| {
+        asm.ipush(1);
+        asm.ipush(0);
+        asm.op(Opcode::IDiv);
+    }, VmConfig::new());
+
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u32(&records[0]), ExitStatus::DivideByZero as u32);
+}
+
+#[test]
+fn integrity_and_limit_errors_bypass_handlers() {
+    // note: 配列の範囲外アクセス
+    let (status, records) = trap_in_try(|asm| {
+        asm.op(Opcode::IAPush).u64(1);
+        asm.lpush(1);
+        asm.op(Opcode::IALoad);
+    }, VmConfig::new());
+
+    assert_eq!(status, ExitStatus::ArrayAccessViolation);
+    assert!(records.is_empty());
+
+    // note: ヒープの上限超過
+    let (status, records) = trap_in_try(|asm| {
+        asm.op(Opcode::BAPush).u64(100);
+    }, VmConfig::new().with_max_heap_size(Some(50)));
+
+    assert_eq!(status, ExitStatus::HeapLimitExceeded);
+    assert!(records.is_empty());
+
+    // note: 未知のコール番号
+    let (status, records) = trap_in_try(|asm| {
+        asm.call(0xfe);
+    }, VmConfig::new());
+
+    assert_eq!(status, ExitStatus::UnknownCallNumber);
+    assert!(records.is_empty());
+}
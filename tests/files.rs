@@ -0,0 +1,105 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+use std::path::{Path, PathBuf};
+
+const OPEN: u8 = 0x20;
+const READ: u8 = 0x21;
+const WRITE: u8 = 0x22;
+const CLOSE: u8 = 0x23;
+
+fn temp_path(name: &str) -> PathBuf {
+    return std::env::temp_dir().join(format!("rustnut_{}_{}", std::process::id(), name));
+}
+
+// note: LdStr で参照するバイト列のプール要素を追加
+fn str_item(asm: &mut Asm, bytes: &[u8]) -> u64 {
+    let mut item = (bytes.len() as u64).to_le_bytes().to_vec();
+    item.extend(bytes);
+    return asm.item(&item);
+}
+
+fn open_file(asm: &mut Asm, path: &Path, mode: u32) {
+    let path_i = str_item(asm, path.to_str().unwrap().as_bytes());
+    asm.op(Opcode::LdStr).u64(path_i);
+    asm.ipush(mode);
+    asm.call(OPEN);
+}
+
+fn file_config(io_error_policy: IoErrorPolicy) -> VmConfig {
+    return VmConfig::new().with_file_calls(true).with_io_error_policy(io_error_policy);
+}
+
+#[test]
+fn file_calls_require_permission() {
+    let mut asm = Asm::new();
+    open_file(&mut asm, &temp_path("denied"), 0);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::CallNotPermitted);
+}
+
+#[test]
+fn write_and_read_through_handles() {
+    let path = temp_path("roundtrip");
+
+    let mut asm = Asm::new();
+    let content_i = str_item(&mut asm, b"hello");
+    open_file(&mut asm, &path, 1);
+    asm.op(Opcode::Dup);
+    asm.op(Opcode::LdStr).u64(content_i);
+    asm.call(WRITE);
+    asm.call(RECORD_U32);
+    asm.call(CLOSE);
+    // note: 読み込みは配列サイズ (8 バイト) を上限とし、ファイル末尾までのバイト数を返す
+    open_file(&mut asm, &path, 0);
+    asm.op(Opcode::BAPush).u64(8);
+    asm.call(READ);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), file_config(IoErrorPolicy::Retry));
+    let written = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(written, b"hello");
+    assert_eq!(as_u32(&records[0]), 5);
+    assert_eq!(as_u32(&records[1]), 5);
+}
+
+#[test]
+fn failed_open_follows_policy() {
+    // note: 存在しないファイルの読み込み
+    let path = temp_path("missing");
+
+    for (io_error_policy, expected_status) in [(IoErrorPolicy::Retry, ExitStatus::IoFailed), (IoErrorPolicy::Trap, ExitStatus::IoFailed), (IoErrorPolicy::ReturnCount, ExitStatus::Success)] {
+        let mut asm = Asm::new();
+        open_file(&mut asm, &path, 0);
+        asm.call(RECORD_U32);
+        asm.op(Opcode::Exit);
+
+        let (status, records) = run_recorded(&asm.build(), file_config(io_error_policy));
+        assert_eq!(status, expected_status);
+
+        if expected_status == ExitStatus::Success {
+            assert_eq!(as_u32(&records[0]), u32::MAX);
+        }
+    }
+}
+
+#[test]
+fn unknown_handles_always_fail() {
+    // note: ハンドルの誤りは ReturnCount でもバイトコード側に返さない
+    for io_error_policy in [IoErrorPolicy::Retry, IoErrorPolicy::Trap, IoErrorPolicy::ReturnCount] {
+        let mut asm = Asm::new();
+        asm.ipush(5);
+        asm.op(Opcode::BAPush).u64(1);
+        asm.call(READ);
+        asm.op(Opcode::Exit);
+
+        assert_eq!(run_recorded(&asm.build(), file_config(io_error_policy)).0, ExitStatus::IoFailed);
+    }
+}
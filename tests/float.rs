@@ -0,0 +1,75 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+fn f2i(value: f32, config: VmConfig) -> (ExitStatus, Option<i32>) {
+    let mut asm = Asm::new();
+    asm.op(Opcode::FPush).f32(value);
+    asm.op(Opcode::F2I);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), config);
+    return (status, records.first().map(|bytes| as_u32(bytes) as i32));
+}
+
+fn d2l(value: f64, config: VmConfig) -> (ExitStatus, Option<i64>) {
+    let mut asm = Asm::new();
+    asm.op(Opcode::DPush).f64(value);
+    asm.op(Opcode::D2L);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), config);
+    return (status, records.first().map(|bytes| as_u64(bytes) as i64));
+}
+
+fn fdiv(left: f32, right: f32, config: VmConfig) -> (ExitStatus, Option<f32>) {
+    let mut asm = Asm::new();
+    asm.op(Opcode::FPush).f32(left);
+    asm.op(Opcode::FPush).f32(right);
+    asm.op(Opcode::FDiv);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), config);
+    return (status, records.first().map(|bytes| f32::from_bits(as_u32(bytes))));
+}
+
+#[test]
+fn float_to_int_trap() {
+    assert_eq!(f2i(-2.7, VmConfig::new()), (ExitStatus::Success, Some(-2)));
+    assert_eq!(f2i(f32::NAN, VmConfig::new()).0, ExitStatus::InvalidFloatOperation);
+    assert_eq!(f2i(3e9, VmConfig::new()).0, ExitStatus::ArithmeticOverflow);
+    assert_eq!(d2l(-1e19, VmConfig::new()).0, ExitStatus::ArithmeticOverflow);
+}
+
+#[test]
+fn float_to_int_saturate() {
+    let config = VmConfig::new().with_float_to_int_policy(FloatToIntPolicy::Saturate);
+    assert_eq!(f2i(-2.7, config.clone()), (ExitStatus::Success, Some(-2)));
+    assert_eq!(f2i(f32::NAN, config.clone()), (ExitStatus::Success, Some(0)));
+    assert_eq!(f2i(3e9, config.clone()), (ExitStatus::Success, Some(i32::MAX)));
+    assert_eq!(f2i(f32::NEG_INFINITY, config.clone()), (ExitStatus::Success, Some(i32::MIN)));
+    assert_eq!(d2l(-1e19, config), (ExitStatus::Success, Some(i64::MIN)));
+}
+
+#[test]
+fn nan_propagates_by_default() {
+    for (left, right) in [(f32::NAN, 2.0), (0.0, 0.0)] {
+        let (status, value) = fdiv(left, right, VmConfig::new());
+        assert_eq!(status, ExitStatus::Success);
+        assert!(value.unwrap().is_nan());
+    }
+}
+
+#[test]
+fn nan_input_propagates_under_trap() {
+    let config = VmConfig::new().with_trap_on_nan(true);
+    let (status, value) = fdiv(f32::NAN, 2.0, config.clone());
+    assert_eq!(status, ExitStatus::Success);
+    assert!(value.unwrap().is_nan());
+    // note: NaN でない入力から生成した場合のみ終了
+    assert_eq!(fdiv(0.0, 0.0, config).0, ExitStatus::InvalidFloatOperation);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+#[test]
+fn imports_resolve_by_name() {
+    // note: 登録番号 (record_u64 は 0x81) と異なる順序でインポート
+    let mut asm = Asm::new();
+    let record_u64 = asm.import("record_u64", 8, 0);
+    let record_u32 = asm.import("record_u32", 4, 0);
+    assert_eq!(record_u64, HOST_CALL_NUMBER_START);
+
+    asm.lpush(7);
+    asm.call(record_u64);
+    asm.ipush(9);
+    asm.call(record_u32);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u64(&records[0]), 7);
+    assert_eq!(as_u32(&records[1]), 9);
+}
+
+#[test]
+fn unresolved_imports_are_rejected() {
+    for (name, arg_size) in [("record_u16", 2), ("record_u32", 8)] {
+        let mut asm = Asm::new();
+        asm.import(name, arg_size, 0);
+        asm.op(Opcode::Exit);
+
+        let module = asm.build();
+        let vm = Vm::with_config(with_recorders(VmConfig::new()));
+        assert!(!module.diagnostics().has_errors());
+        assert!(vm.diagnostics(&module).has_errors());
+        assert_eq!(vm.run_module(&module), ExitStatus::InvalidBytecode);
+    }
+}
+
+#[test]
+fn numbers_beyond_imports_are_unknown() {
+    // note: インポート表を持つモジュールでは登録番号では呼び出せない
+    let mut asm = Asm::new();
+    asm.import("record_u64", 8, 0);
+    asm.ipush(1);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::UnknownCallNumber);
+}
+
+fn intern_large(context: &mut HostContext, _args: &[u8]) -> Option<Vec<u8>> {
+    let handle = context.intern(&[0u8; 64])?;
+    return Some(handle.to_ne_bytes().to_vec());
+}
+
+#[test]
+fn interned_constants_are_charged_to_heap() {
+    for (max_heap_size, expected_status) in [(None, ExitStatus::Success), (Some(64), ExitStatus::HostCallFailed)] {
+        let mut config = VmConfig::new().with_max_heap_size(max_heap_size);
+        config.register_host_call(HostCall {
+            name: "intern_large".to_string(),
+            number: 0x90,
+            arg_size: 0,
+            ret_size: 4,
+            handler: intern_large,
+        }).unwrap();
+
+        let mut asm = Asm::new();
+        asm.call(0x90);
+        asm.op(Opcode::Exit);
+
+        assert_eq!(run_recorded(&asm.build(), config).0, expected_status);
+    }
+
+    // note: 埋め込み側で登録した定数も実行前に計上
+    let mut config = VmConfig::new().with_max_heap_size(Some(64));
+    config.register_constant(vec![0u8; 64]);
+
+    let mut asm = Asm::new();
+    asm.op(Opcode::Exit);
+    assert_eq!(run_recorded(&asm.build(), config).0, ExitStatus::HeapLimitExceeded);
+}
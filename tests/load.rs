@@ -0,0 +1,147 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+fn error_fields(module: &Module) -> Vec<&'static str> {
+    return module.diagnostics().items.iter().filter(|each_item| each_item.level == DiagnosticLevel::Error).map(|each_item| each_item.field).collect();
+}
+
+#[test]
+fn invoked_descriptors_are_checked_at_load() {
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 1, 2, &[]);
+    // note: 参照されない関数は検査しない
+    asm.func("unused", 0, 3, &[]);
+
+    asm.ipush(1);
+    asm.ipush(2);
+    asm.invoke(callee_i);
+    asm.op(Opcode::Exit);
+    asm.label("callee");
+    asm.label("unused");
+    asm.op(Opcode::Ret);
+
+    let module = asm.build();
+    assert_eq!(error_fields(&module), vec!["arg_len"]);
+    assert_eq!(Vm::new().run_module(&module), ExitStatus::InvalidBytecode);
+}
+
+#[test]
+fn slot_kinds_are_checked_at_load() {
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 1, 0, &[5]);
+    asm.descriptor_flags(DESCRIPTOR_FLAG_SLOT_KINDS);
+
+    asm.jump(Opcode::Goto, "call");
+    asm.label("callee");
+    asm.op(Opcode::Ret);
+    // note: 分岐先からの呼び出しも検査対象
+    asm.label("call");
+    asm.invoke(callee_i);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(error_fields(&asm.build()), vec!["slot kind"]);
+}
+
+#[test]
+fn branches_must_stay_in_bytecode() {
+    let mut asm = Asm::new();
+    asm.ipush(0);
+    asm.op(Opcode::If).u16(0x7fff);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(error_fields(&asm.build()), vec!["branch target"]);
+}
+
+#[test]
+fn calls_outside_allow_list_are_rejected_at_load() {
+    for (code, fields) in [(RECORD_U32, vec![]), (RECORD_U64, vec!["call number"])] {
+        let mut asm = Asm::new();
+        let callee_i = asm.func("callee", 0, 0, &[1, RECORD_U32]);
+        asm.descriptor_flags(DESCRIPTOR_FLAG_CALL_ALLOW_LIST);
+
+        asm.invoke(callee_i);
+        asm.op(Opcode::Exit);
+        asm.label("callee");
+        asm.ipush(1);
+        asm.call(code);
+        asm.op(Opcode::Ret);
+
+        assert_eq!(error_fields(&asm.build()), fields);
+    }
+}
+
+#[test]
+fn vtable_methods_are_checked_at_load() {
+    // note: vtable からのみ参照されるメソッドも検査
+    let mut asm = Asm::new();
+    let method_i = asm.func("method", 1, 2, &[]);
+    asm.vtable(&[method_i]);
+
+    asm.op(Opcode::Exit);
+    asm.label("method");
+    asm.op(Opcode::Ret);
+
+    assert_eq!(error_fields(&asm.build()), vec!["arg_len"]);
+}
+
+#[test]
+fn invoke_virtual_requires_vtable_list() {
+    let mut asm = Asm::new();
+    asm.op(Opcode::LAPush).u64(1);
+    asm.op(Opcode::InvokeVirtual).u16(0);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(error_fields(&asm.build()), vec!["vtable list"]);
+}
+
+#[test]
+fn invoke_dynamic_requires_function_reference() {
+    // note: FuncRef で参照されない関数 (Invoke のみで参照) はプールインデックスを直接指定しても呼び出せない
+    for (take_ref, expected_status) in [(true, ExitStatus::Success), (false, ExitStatus::InvalidFunctionDescriptor)] {
+        let mut asm = Asm::new();
+        let callee_i = asm.func("callee", 0, 0, &[]);
+
+        if take_ref {
+            asm.op(Opcode::FuncRef).u64(callee_i);
+            asm.op(Opcode::InvokeDynamic);
+        } else {
+            asm.invoke(callee_i);
+            asm.lpush(callee_i);
+            asm.op(Opcode::InvokeDynamic);
+        }
+
+        asm.op(Opcode::Exit);
+        asm.label("callee");
+        asm.op(Opcode::Ret);
+
+        let module = asm.build();
+        assert!(!module.diagnostics().has_errors());
+        assert_eq!(Vm::new().run_module(&module), expected_status);
+    }
+}
+
+#[test]
+fn indirect_targets_follow_allow_list_at_load() {
+    // note: vtable と MakeClosure からのみ参照される関数も許可リストを検査
+    for use_vtable in [true, false] {
+        let mut asm = Asm::new();
+        let callee_i = asm.func("callee", 2, 2, &[1, RECORD_U32]);
+        asm.descriptor_flags(DESCRIPTOR_FLAG_CALL_ALLOW_LIST);
+
+        if use_vtable {
+            asm.vtable(&[callee_i]);
+        } else {
+            asm.op(Opcode::MakeClosure).u64(callee_i).u8(0);
+        }
+
+        asm.op(Opcode::Exit);
+        asm.label("callee");
+        asm.lpush(1);
+        asm.call(RECORD_U64);
+        asm.op(Opcode::Ret);
+
+        assert_eq!(error_fields(&asm.build()), vec!["call number"]);
+    }
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 呼び出し先で確保した配列 X (変数 2-3) の参照を store で保存して戻る (変数 0-1 は呼び出し元が確保した 8 バイトの配列)
+// note: 戻った後の確保が max_heap_size を超えるかどうかで X が解放されなかったかを判定
+fn escape_on_return(asm: &mut Asm, callee_i: u64, store: impl Fn(&mut Asm)) -> ExitStatus {
+    asm.op(Opcode::LAPush).u64(1);
+    asm.invoke(callee_i);
+    asm.op(Opcode::BAPush).u64(100);
+    asm.op(Opcode::Exit);
+
+    asm.label("callee");
+    asm.op(Opcode::BAPush).u64(100);
+    asm.op(Opcode::Store2).u16(2);
+    store(asm);
+
+    let config = VmConfig::new().with_auto_drop_arrays(true).with_max_heap_size(Some(200));
+    return run_recorded(&asm.build(), config).0;
+}
+
+#[test]
+fn unescaped_array_is_dropped() {
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 4, 2, &[]);
+
+    let status = escape_on_return(&mut asm, callee_i, |asm| {
+        asm.op(Opcode::Ret);
+    });
+
+    assert_eq!(status, ExitStatus::Success);
+}
+
+#[test]
+fn half_stores_escape() {
+    // note: 2 回の IAStore で呼び出し元の配列に書き込み
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 4, 2, &[]);
+
+    let status = escape_on_return(&mut asm, callee_i, |asm| {
+        for half in 0..2 {
+            asm.op(Opcode::Load2).u16(0);
+            asm.lpush(half);
+            asm.op(Opcode::Load).u16(2 + half as u16);
+            asm.op(Opcode::IAStore);
+        }
+
+        asm.op(Opcode::Ret);
+    });
+
+    assert_eq!(status, ExitStatus::HeapLimitExceeded);
+
+    // note: 2 回の GStore でグローバル変数に書き込み
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 4, 2, &[]);
+    asm.globals(2);
+
+    let status = escape_on_return(&mut asm, callee_i, |asm| {
+        for half in 0..2 {
+            asm.op(Opcode::Load).u16(2 + half);
+            asm.op(Opcode::GStore).u16(half);
+        }
+
+        asm.op(Opcode::Ret);
+    });
+
+    assert_eq!(status, ExitStatus::HeapLimitExceeded);
+}
+
+#[test]
+fn split_return_values_keep_array() {
+    // note: 上位・下位の語の間に別の値を挟んで 3 要素を返す
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 4, 2, &[3]);
+    asm.descriptor_flags(DESCRIPTOR_FLAG_RET_LEN);
+
+    let status = escape_on_return(&mut asm, callee_i, |asm| {
+        asm.op(Opcode::Load).u16(2);
+        asm.ipush(0);
+        asm.op(Opcode::Load).u16(3);
+        asm.op(Opcode::Ret);
+    });
+
+    assert_eq!(status, ExitStatus::HeapLimitExceeded);
+}
+
+// note: X の参照を 1 バイトずつ BAStore で src (変数 4-5) に書き込み (エスケープとして検出されない)、ArrayCopy で呼び出し元の配列 dst にコピーして戻る
+fn copy_reference(dst_off: u64) -> ExitStatus {
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 6, 2, &[]);
+
+    return escape_on_return(&mut asm, callee_i, |asm| {
+        asm.op(Opcode::BAPush).u64(8);
+        asm.op(Opcode::Store2).u16(4);
+
+        for byte_i in 0..8 {
+            asm.op(Opcode::Load2).u16(4);
+            asm.lpush(byte_i);
+            asm.op(Opcode::Load2).u16(2);
+            asm.ipush(byte_i as u32 * 8);
+            asm.op(Opcode::LShr);
+            asm.op(Opcode::L2I);
+            asm.op(Opcode::BAStore);
+        }
+
+        asm.op(Opcode::Load2).u16(0);
+        asm.lpush(dst_off);
+        asm.op(Opcode::Load2).u16(4);
+        asm.lpush(0);
+        asm.lpush(8 - dst_off);
+        asm.op(Opcode::ArrayCopy);
+        asm.op(Opcode::Ret);
+    });
+}
+
+#[test]
+fn array_copy_escapes_reference_at_any_offset() {
+    // note: X は dst から参照されるため解放されず、2 回目の確保で上限を超える
+    for dst_off in 0..4 {
+        assert_eq!(copy_reference(dst_off), ExitStatus::HeapLimitExceeded);
+    }
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 戻り値の要素数 ret_len の関数が values をプッシュして Ret し、呼び出し元は record_len 個の値を記録
+fn ret_values(ret_len: u8, values: &[u32], record_len: usize) -> (ExitStatus, Vec<u32>) {
+    let mut asm = Asm::new();
+    let callee_i = asm.func("callee", 0, 0, &[ret_len]);
+    asm.descriptor_flags(DESCRIPTOR_FLAG_RET_LEN);
+
+    asm.invoke(callee_i);
+
+    for _ in 0..record_len {
+        asm.call(RECORD_U32);
+    }
+
+    asm.op(Opcode::Exit);
+    asm.label("callee");
+
+    for each_value in values {
+        asm.ipush(*each_value);
+    }
+
+    asm.op(Opcode::Ret);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    return (status, records.iter().map(|each_record| as_u32(&each_record[..])).collect());
+}
+
+#[test]
+fn declared_values_are_returned_in_order() {
+    assert_eq!(ret_values(3, &[1, 2, 3], 3), (ExitStatus::Success, vec![3, 2, 1]));
+}
+
+#[test]
+fn values_beyond_ret_len_are_discarded() {
+    assert_eq!(ret_values(2, &[9, 1, 2], 2), (ExitStatus::Success, vec![2, 1]));
+    // note: 呼び出し元のオペランドスタックには宣言された数の値のみが残る
+    assert_eq!(ret_values(2, &[9, 1, 2], 3).0, ExitStatus::StackAccessViolation);
+}
+
+#[test]
+fn missing_values_are_access_violation() {
+    assert_eq!(ret_values(2, &[1], 0).0, ExitStatus::StackAccessViolation);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+fn alloc_twice(drop_first: bool) -> ExitStatus {
+    let mut asm = Asm::new();
+    asm.op(Opcode::BAPush).u64(50);
+
+    if drop_first {
+        asm.op(Opcode::Drop);
+    }
+
+    asm.op(Opcode::BAPush).u64(50);
+    asm.op(Opcode::Exit);
+    return run_recorded(&asm.build(), VmConfig::new().with_max_heap_size(Some(100))).0;
+}
+
+#[test]
+fn stack_size_must_hold_entry_frame() {
+    assert!(VmConfig::new().with_stack_size(8).validate_stack_size().is_err());
+    assert!(VmConfig::new().with_stack_size(16).validate_stack_size().is_ok());
+
+    let mut asm = Asm::new();
+    asm.op(Opcode::Exit);
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new().with_stack_size(8)).0, ExitStatus::StackOverflow);
+}
+
+#[test]
+fn minimal_stack_overflows_on_first_push() {
+    let mut asm = Asm::new();
+    asm.ipush(1);
+    asm.op(Opcode::Exit);
+
+    let config = VmConfig::new().with_stack_size(16);
+    assert_eq!(run_recorded(&asm.build(), config).0, ExitStatus::StackOverflow);
+}
+
+#[test]
+fn heap_limit() {
+    assert_eq!(alloc_twice(false), ExitStatus::HeapLimitExceeded);
+    // note: 解放した分は再び確保できる
+    assert_eq!(alloc_twice(true), ExitStatus::Success);
+}
+
+#[test]
+fn oversized_array_exceeds_heap() {
+    // note: 要素数 × 要素サイズが usize に収まらない
+    let mut asm = Asm::new();
+    asm.op(Opcode::LAPush).u64(u64::MAX / 4);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::HeapLimitExceeded);
+}
+
+#[test]
+fn length_proportional_ops_consume_fuel() {
+    // note: 1024 バイトの埋め込み・コピーは 128 命令分, 256 要素のベクトル演算は 256 命令分
+    let fill = |asm: &mut Asm| {
+        asm.op(Opcode::IAPush).u64(256);
+        asm.ipush(1);
+        asm.lpush(256);
+        asm.op(Opcode::IAFill);
+    };
+
+    let copy = |asm: &mut Asm| {
+        asm.op(Opcode::BAPush).u64(1024);
+        asm.lpush(0);
+        asm.op(Opcode::BAPush).u64(1024);
+        asm.lpush(0);
+        asm.lpush(1024);
+        asm.op(Opcode::ArrayCopy);
+    };
+
+    let vector = |asm: &mut Asm| {
+        for _ in 0..3 {
+            asm.op(Opcode::IAPush).u64(256);
+        }
+
+        asm.lpush(256);
+        asm.op(Opcode::IVAdd);
+    };
+
+    for (emit, max_insts) in [(&fill as &dyn Fn(&mut Asm), 100), (&copy, 100), (&vector, 200)] {
+        for (max_insts, expected_status) in [(max_insts, ExitStatus::FuelExhausted), (1000, ExitStatus::Success)] {
+            let mut asm = Asm::new();
+            emit(&mut asm);
+            asm.op(Opcode::Exit);
+
+            let config = VmConfig::new().with_max_instructions(Some(max_insts));
+            assert_eq!(run_recorded(&asm.build(), config).0, expected_status);
+        }
+    }
+}
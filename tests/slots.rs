@@ -0,0 +1,69 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 変数 0 (Int), 1 (Int), 2 (Long), 3 (Ref) を持ち、変数 0-2 を引数として受け取る関数を呼び出す
+fn invoke_typed(body: impl Fn(&mut Asm)) -> (ExitStatus, Vec<Vec<u8>>) {
+    let mut asm = Asm::new();
+    let func_i = asm.func("func", 4, 3, &[0, 0, 1, 4]);
+    asm.descriptor_flags(DESCRIPTOR_FLAG_SLOT_KINDS);
+
+    asm.ipush(7);
+    asm.ipush(8);
+    asm.lpush(0x1_0000_0002);
+    asm.invoke(func_i);
+    asm.op(Opcode::Exit);
+
+    asm.label("func");
+    body(&mut asm);
+    asm.op(Opcode::Ret);
+
+    return run_recorded(&asm.build(), VmConfig::new());
+}
+
+#[test]
+fn arguments_keep_declared_widths() {
+    let (status, records) = invoke_typed(|asm| {
+        asm.op(Opcode::Load2).u16(2);
+        asm.call(RECORD_U64);
+        asm.op(Opcode::Load).u16(0);
+        asm.call(RECORD_U32);
+        asm.op(Opcode::Load).u16(1);
+        asm.call(RECORD_U32);
+        // note: Ref 変数は usize 幅
+        asm.op(Opcode::IAPush).u64(1);
+        asm.op(Opcode::Store2).u16(3);
+        asm.op(Opcode::Load2).u16(3);
+        asm.lpush(0);
+        asm.ipush(5);
+        asm.op(Opcode::IAStore);
+        asm.op(Opcode::Load2).u16(3);
+        asm.lpush(0);
+        asm.op(Opcode::IALoad);
+        asm.call(RECORD_U32);
+    });
+
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u64(&records[0]), 0x1_0000_0002);
+    assert_eq!(as_u32(&records[1]), 7);
+    assert_eq!(as_u32(&records[2]), 8);
+    assert_eq!(as_u32(&records[3]), 5);
+}
+
+#[test]
+fn mismatched_width_is_access_violation() {
+    // note: Long 変数の半分のみの読み書きは不可
+    let (status, _) = invoke_typed(|asm| {
+        asm.op(Opcode::Load).u16(2);
+    });
+
+    assert_eq!(status, ExitStatus::StackAccessViolation);
+
+    let (status, _) = invoke_typed(|asm| {
+        asm.ipush(0);
+        asm.op(Opcode::Store).u16(2);
+    });
+
+    assert_eq!(status, ExitStatus::StackAccessViolation);
+}
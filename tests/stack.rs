@@ -0,0 +1,87 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+const PAD_BYTE: u8 = 0x90;
+
+// note: 1 バイトの値を返すホストコール (スタックを 4 バイト境界からずらすため)
+fn pad_byte(_context: &mut HostContext, _args: &[u8]) -> Option<Vec<u8>> {
+    return Some(vec![0]);
+}
+
+// note: 呼び出し前に 1 バイト値を積み、変数テーブルを境界に揃っていない位置に置く (1 - 7 バイトのずれを検査)
+#[test]
+fn locals_at_any_alignment() {
+    for padding in 0..8 {
+        let mut asm = Asm::new();
+        let func_i = asm.func("func", 3, 1, &[]);
+
+        for _ in 0..padding {
+            asm.call(PAD_BYTE);
+        }
+
+        asm.ipush(40);
+        asm.invoke(func_i);
+        asm.op(Opcode::Exit);
+
+        asm.label("func");
+        asm.op(Opcode::Inc).u16(0).u8(2);
+        asm.op(Opcode::Load).u16(0);
+        asm.call(RECORD_U32);
+        asm.lpush(u64::MAX - 1);
+        asm.op(Opcode::Store2).u16(1);
+        asm.op(Opcode::Load2).u16(1);
+        asm.call(RECORD_U64);
+        asm.op(Opcode::Ret);
+
+        let mut config = VmConfig::new();
+        config.register_host_call(HostCall {
+            name: "pad_byte".to_string(),
+            number: PAD_BYTE,
+            arg_size: 0,
+            ret_size: 1,
+            handler: pad_byte,
+        }).unwrap();
+
+        let (status, records) = run_recorded(&asm.build(), config);
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(as_u32(&records[0]), 42);
+        assert_eq!(as_u64(&records[1]), u64::MAX - 1);
+    }
+}
+
+#[test]
+fn swap_exchanges_top_values() {
+    let mut asm = Asm::new();
+    asm.ipush(1);
+    asm.ipush(2);
+    asm.op(Opcode::Swap);
+    asm.call(RECORD_U32);
+    asm.call(RECORD_U32);
+    // note: Swap2 は 64 ビット値を 1 要素として交換 (上位・下位の語は入れ替えない)
+    asm.lpush(u64::MAX - 1);
+    asm.lpush(3);
+    asm.op(Opcode::Swap2);
+    asm.call(RECORD_U64);
+    asm.call(RECORD_U64);
+    asm.op(Opcode::Exit);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u32(&records[0]), 1);
+    assert_eq!(as_u32(&records[1]), 2);
+    assert_eq!(as_u64(&records[2]), u64::MAX - 1);
+    assert_eq!(as_u64(&records[3]), 3);
+}
+
+#[test]
+fn swap_needs_two_values() {
+    // note: フレーム内の値が 1 つの場合はフレームの境界を越えてポップしない
+    let mut asm = Asm::new();
+    asm.ipush(1);
+    asm.op(Opcode::Swap);
+    asm.op(Opcode::Exit);
+
+    assert_eq!(run_recorded(&asm.build(), VmConfig::new()).0, ExitStatus::StackAccessViolation);
+}
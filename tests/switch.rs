@@ -0,0 +1,43 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: キー 1 - 3 の分岐先とデフォルトの分岐先でそれぞれの値を記録 (各分岐先は 8 バイト)
+fn table_switch(asm: &mut Asm, key: u32, entry_offsets: &[i16; 3]) {
+    asm.ipush(key);
+    asm.op(Opcode::TableSwitch).u16(24).u32(1).u32(3);
+
+    for each_offset in entry_offsets {
+        asm.u16(*each_offset as u16);
+    }
+
+    for value in [1, 2, 3, 0] {
+        asm.ipush(value);
+        asm.call(RECORD_U32);
+        asm.op(Opcode::Exit);
+    }
+}
+
+#[test]
+fn table_switch_selects_entry() {
+    for (key, value) in [(0, 0), (1, 1), (2, 2), (3, 3), (4, 0)] {
+        let mut asm = Asm::new();
+        table_switch(&mut asm, key, &[0, 8, 16]);
+
+        let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(as_u32(&records[0]), value);
+    }
+}
+
+#[test]
+fn table_entries_are_checked_at_load() {
+    // note: 選択されない要素も読み込み時に検査
+    let mut asm = Asm::new();
+    table_switch(&mut asm, 1, &[0, 8, 0x7fff]);
+
+    let module = asm.build();
+    assert!(module.diagnostics().items.iter().any(|each_item| each_item.field == "branch target"));
+    assert_eq!(run_recorded(&module, VmConfig::new()).0, ExitStatus::InvalidBytecode);
+}
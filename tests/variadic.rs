@@ -0,0 +1,72 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: 追加の引数 values を渡して可変長引数関数を呼び出す
+fn invoke_variadic(asm: &mut Asm, func_i: u64, values: &[u32]) {
+    for each_value in values {
+        asm.ipush(*each_value);
+    }
+
+    asm.ipush(values.len() as u32);
+    asm.invoke(func_i);
+}
+
+#[test]
+fn extra_args_are_packed_into_array() {
+    // note: 呼び出し先は変数 0-1 の配列の要素 1 を記録
+    let mut asm = Asm::new();
+    let func_i = asm.func("func", 2, 0x80, &[]);
+    invoke_variadic(&mut asm, func_i, &[10, 20, 30]);
+    asm.op(Opcode::Exit);
+
+    asm.label("func");
+    asm.op(Opcode::Load2).u16(0);
+    asm.lpush(1);
+    asm.op(Opcode::IALoad);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Ret);
+
+    let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u32(&records[0]), 20);
+}
+
+#[test]
+fn extra_args_array_drops_with_callee() {
+    // note: 配列 (40 バイト) が呼び出し元に残れば 3 回目で上限を超える
+    let mut asm = Asm::new();
+    let func_i = asm.func("func", 2, 0x80, &[]);
+
+    for _ in 0..5 {
+        invoke_variadic(&mut asm, func_i, &[0; 8]);
+    }
+
+    asm.op(Opcode::Exit);
+    asm.label("func");
+    asm.op(Opcode::Ret);
+
+    let config = VmConfig::new().with_auto_drop_arrays(true).with_max_heap_size(Some(100));
+    assert_eq!(run_recorded(&asm.build(), config).0, ExitStatus::Success);
+}
+
+#[test]
+fn returned_extra_args_array_survives() {
+    let mut asm = Asm::new();
+    let func_i = asm.func("func", 2, 0x80, &[]);
+    invoke_variadic(&mut asm, func_i, &[7, 9]);
+    asm.lpush(1);
+    asm.op(Opcode::IALoad);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+
+    asm.label("func");
+    asm.op(Opcode::Load2).u16(0);
+    asm.op(Opcode::LRet);
+
+    let config = VmConfig::new().with_auto_drop_arrays(true);
+    let (status, records) = run_recorded(&asm.build(), config);
+    assert_eq!(status, ExitStatus::Success);
+    assert_eq!(as_u32(&records[0]), 9);
+}
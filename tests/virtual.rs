@@ -0,0 +1,73 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+// note: vtable (メソッド 0 は 10、メソッド 1 は 20 を記録) を持つオブジェクトを作成し、method_i のメソッドを呼び出す
+fn invoke_method(method_i: u16) -> (ExitStatus, RunReport, Vec<Vec<u8>>) {
+    let mut asm = Asm::new();
+    let method_pool_indexes = ["method0", "method1"].map(|label| asm.func(label, 2, 2, &[]));
+    let vtable_i = asm.vtable(&method_pool_indexes);
+
+    // note: オブジェクトの先頭 8 バイトに vtable のプールインデックスを書き込み
+    asm.op(Opcode::LAPush).u64(1);
+    asm.op(Opcode::Dup2);
+    asm.lpush(0);
+    asm.lpush(vtable_i);
+    asm.op(Opcode::LAStore);
+    asm.op(Opcode::InvokeVirtual).u16(method_i);
+    asm.op(Opcode::Exit);
+
+    for (label, value) in [("method0", 10), ("method1", 20)] {
+        asm.label(label);
+        asm.ipush(value);
+        asm.call(RECORD_U32);
+        asm.op(Opcode::Ret);
+    }
+
+    let mut records = Vec::<Vec<u8>>::new();
+    let (status, report) = Vm::with_config(with_recorders(VmConfig::new())).run_module_with_user_data(&asm.build(), &mut records);
+    return (status, report, records);
+}
+
+#[test]
+fn methods_dispatch_through_vtable() {
+    for (method_i, value) in [(0, 10), (1, 20)] {
+        let (status, _, records) = invoke_method(method_i);
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(as_u32(&records[0]), value);
+    }
+}
+
+#[test]
+fn missing_method_is_layout_error() {
+    let (status, report, records) = invoke_method(2);
+    assert_eq!(status, ExitStatus::InvalidLayoutDescriptor);
+    assert_eq!(report.descriptor_error().map(|error| error.field), Some("method_len"));
+    assert!(records.is_empty());
+}
+
+#[test]
+fn unlisted_vtable_is_layout_error() {
+    // note: vtable と同じ形式でも一覧に含まれないプール要素は vtable として扱わない
+    let mut asm = Asm::new();
+    let method_i = asm.func("method", 2, 2, &[]);
+    asm.vtable(&[method_i]);
+    let mut unlisted_vtable = 1u16.to_le_bytes().to_vec();
+    unlisted_vtable.extend(&method_i.to_le_bytes());
+    let unlisted_vtable_i = asm.item(&unlisted_vtable);
+
+    asm.op(Opcode::LAPush).u64(1);
+    asm.op(Opcode::Dup2);
+    asm.lpush(0);
+    asm.lpush(unlisted_vtable_i);
+    asm.op(Opcode::LAStore);
+    asm.op(Opcode::InvokeVirtual).u16(0);
+    asm.op(Opcode::Exit);
+    asm.label("method");
+    asm.op(Opcode::Ret);
+
+    let (status, report) = Vm::new().run_module_with_report(&asm.build());
+    assert_eq!(status, ExitStatus::InvalidLayoutDescriptor);
+    assert_eq!(report.descriptor_error().map(|error| error.field), Some("vtable"));
+}
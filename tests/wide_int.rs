@@ -0,0 +1,64 @@
+mod common;
+
+use common::*;
+use rustnut::*;
+
+fn q_calc(left: u128, right: u128, opcode: Opcode) -> (ExitStatus, Vec<Vec<u8>>) {
+    let mut asm = Asm::new();
+    // note: スタック上の 128 ビット値が境界に揃わないよう 4 バイトずらす
+    asm.ipush(7);
+    asm.op(Opcode::QPush).u128(left);
+    asm.op(Opcode::QPush).u128(right);
+    asm.op(opcode);
+    asm.call(RECORD_U128);
+    asm.call(RECORD_U32);
+    asm.op(Opcode::Exit);
+    return run_recorded(&asm.build(), VmConfig::new());
+}
+
+#[test]
+fn qpush_at_any_alignment() {
+    for padding in 0..4 {
+        let mut asm = Asm::new();
+
+        for _ in 0..padding {
+            asm.op(Opcode::BPush).u8(0);
+        }
+
+        asm.op(Opcode::QPush).u128(u128::MAX - 1);
+        asm.op(Opcode::QPush).u128(1 << 100);
+        asm.call(RECORD_U128);
+        asm.call(RECORD_U128);
+        asm.op(Opcode::Exit);
+
+        let (status, records) = run_recorded(&asm.build(), VmConfig::new());
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(as_u128(&records[0]), 1 << 100);
+        assert_eq!(as_u128(&records[1]), u128::MAX - 1);
+    }
+}
+
+#[test]
+fn q_arithmetic() {
+    let big = 1u128 << 96;
+
+    for (opcode, left, right, expected) in [
+        (Opcode::QAdd, big, big + 5, 2 * big + 5),
+        (Opcode::QSub, big + 5, 5, big),
+        (Opcode::QMul, big, 1 << 20, 1 << 116),
+        (Opcode::QDiv, big * 3 + 2, 3, big),
+    ] {
+        let (status, records) = q_calc(left, right, opcode);
+        assert_eq!(status, ExitStatus::Success);
+        assert_eq!(as_u128(&records[0]), expected);
+        assert_eq!(as_u32(&records[1]), 7);
+    }
+}
+
+#[test]
+fn q_traps() {
+    assert_eq!(q_calc(u128::MAX, 1, Opcode::QAdd).0, ExitStatus::ArithmeticOverflow);
+    assert_eq!(q_calc(0, 1, Opcode::QSub).0, ExitStatus::ArithmeticOverflow);
+    assert_eq!(q_calc(u128::MAX, 2, Opcode::QMul).0, ExitStatus::ArithmeticOverflow);
+    assert_eq!(q_calc(1, 0, Opcode::QDiv).0, ExitStatus::DivideByZero);
+}